@@ -0,0 +1,113 @@
+//! `#[derive(CircomInputs)]`, re-exported by `ark-circom-witnesscalc`
+//!
+//! Each named field is an input signal, or a group of them for nested structs,
+//! e.g. `tx.amount`. Rename a field with `#[circom(rename = "nullifierHash")]`.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, parse_quote, Data, DeriveInput, Fields, LitStr};
+
+#[proc_macro_derive(CircomInputs, attributes(circom))]
+pub fn derive_circom_inputs(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &input.ident,
+                    "CircomInputs needs a struct with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "CircomInputs can only be derived for structs",
+            ))
+        }
+    };
+
+    let krate = quote!(::ark_circom_witnesscalc::inputs);
+    let mut signals = Vec::new();
+    let mut inserts = Vec::new();
+    let mut generics = input.generics.clone();
+    generics
+        .params
+        .push(parse_quote!(__F: #krate::__private::PrimeField));
+    let where_clause = generics.make_where_clause();
+    for field in fields {
+        let ident = field.ident.as_ref().expect("named field");
+        let ty = &field.ty;
+        let name = signal_name(field)?;
+        where_clause
+            .predicates
+            .push(parse_quote!(#ty: #krate::CircomInput<__F>));
+        signals.push(quote! {
+            <#ty as #krate::CircomInput<__F>>::signals(
+                &#krate::__private::field_name(name, #name),
+                signals,
+            );
+        });
+        inserts.push(quote! {
+            #krate::CircomInput::<__F>::insert_into(
+                &self.#ident,
+                &#krate::__private::field_name(name, #name),
+                inputs,
+            );
+        });
+    }
+
+    let ident = &input.ident;
+    let (_, ty_generics, _) = input.generics.split_for_impl();
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics #krate::CircomInput<__F> for #ident #ty_generics #where_clause {
+            fn value_len() -> ::core::option::Option<usize> {
+                ::core::option::Option::None
+            }
+
+            // Never called, a struct is a group of signals rather than values
+            fn push_values(&self, _values: &mut ::std::vec::Vec<__F>) {}
+
+            fn signals(name: &str, signals: &mut ::std::vec::Vec<(::std::string::String, usize)>) {
+                #(#signals)*
+            }
+
+            fn insert_into(&self, name: &str, inputs: &mut #krate::Inputs<__F>) {
+                #(#inserts)*
+            }
+        }
+
+        impl #impl_generics #krate::CircomInputs<__F> for #ident #ty_generics #where_clause {}
+    })
+}
+
+/// The field name, or its `#[circom(rename = "...")]`
+fn signal_name(field: &syn::Field) -> syn::Result<String> {
+    let mut name = field
+        .ident
+        .as_ref()
+        .expect("named field")
+        .to_string()
+        .trim_start_matches("r#")
+        .to_string();
+    for attr in field.attrs.iter().filter(|a| a.path().is_ident("circom")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                name = meta.value()?.parse::<LitStr>()?.value();
+                Ok(())
+            } else {
+                Err(meta.error("expected `rename = \"...\"`"))
+            }
+        })?;
+    }
+    Ok(name)
+}
@@ -0,0 +1,982 @@
+use std::io::Cursor;
+use std::process::ExitCode;
+
+use ark_bn254::Bn254;
+use ark_ec::pairing::Pairing;
+use ark_groth16::r1cs_to_qap::{LibsnarkReduction, R1CSToQAP};
+use ark_groth16::VerifyingKey;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+
+use ark_circom_witnesscalc::prover::ProofWithInputs;
+use ark_circom_witnesscalc::snarkjs::is_snarkjs_proof;
+use ark_circom_witnesscalc::utils::calc_witness2_with;
+use ark_circom_witnesscalc::{
+    parse_field_element, proof_and_inputs_from_json, proof_to_calldata, proof_to_eip197,
+    proof_to_json, proof_to_json_with_signals, proof_to_snarkjs_json, public_inputs_from_json,
+    public_inputs_to_json, setup_oneshot, solidity_verifier, verify_proof,
+    verify_proof_json_with_public, verifying_key_from_json, verifying_key_to_json,
+    verifying_key_to_snarkjs_json, witness_from_wtns, witness_to_json, witness_to_wtns, zkey,
+    CircomCircuit, CircomCurve, CircomReduction, Curve, Error, MissingInputs, Prover,
+    PublicSignals, R1CSFile, Reduction, SymbolTable, ZKey,
+};
+
+use crate::{read_input, read_input_string, write_output, Args, CliError, CliResult, REJECTED};
+
+fn serialize<T: CanonicalSerialize>(value: &T) -> CliResult<Vec<u8>> {
+    let mut bytes = Vec::new();
+    value
+        .serialize_uncompressed(&mut bytes)
+        .map_err(|e| CliError::Other(e.to_string()))?;
+    Ok(bytes)
+}
+
+/// The data as a string if it's a JSON object or list
+fn as_json(data: &[u8]) -> Option<&str> {
+    let s = std::str::from_utf8(data).ok()?;
+    s.trim_start().starts_with(['{', '[']).then_some(s)
+}
+
+pub fn setup(args: &Args) -> CliResult {
+    let r1cs_data = read_input(&args.positional(1, 1)?[0])?;
+    let outputs = ["--pkey", "--vk", "--vk-json", "--snarkjs-vk"];
+    if outputs.iter().all(|o| args.get(o).is_none()) {
+        return Err(CliError::Usage(
+            "nothing to write, give at least one of --pkey, --vk, --vk-json or --snarkjs-vk"
+                .to_string(),
+        ));
+    }
+    let reduction = args.reduction()?;
+    with_curve!(args.curve()?, setup_with(args, &r1cs_data, reduction))
+}
+
+fn setup_with<E: CircomCurve>(args: &Args, r1cs_data: &[u8], reduction: Reduction) -> CliResult {
+    let pk = match reduction {
+        Reduction::Libsnark => setup_oneshot::<E, LibsnarkReduction>(r1cs_data)?,
+        Reduction::Circom => setup_oneshot::<E, CircomReduction>(r1cs_data)?,
+    };
+    if let Some(path) = args.get("--pkey") {
+        write_output(path, &serialize(&pk)?)?;
+    }
+    if let Some(path) = args.get("--vk") {
+        write_output(path, &serialize(&pk.vk)?)?;
+    }
+    if let Some(path) = args.get("--vk-json") {
+        write_output(path, verifying_key_to_json(&pk.vk)?.as_bytes())?;
+    }
+    if let Some(path) = args.get("--snarkjs-vk") {
+        write_output(path, verifying_key_to_snarkjs_json(&pk.vk)?.as_bytes())?;
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// The witness is computed from inputs JSON and a graph, or read from a `.wtns`
+enum WitnessSource {
+    Inputs {
+        inputs_data: String,
+        graph_data: Vec<u8>,
+    },
+    Wtns(Vec<u8>),
+}
+
+pub fn prove(args: &Args) -> CliResult {
+    // Usage errors come before reading any file, but the proving key decides
+    // whether --reduction may be given
+    match args.get("--witness") {
+        Some(_) => {
+            args.positional(0, 0)?;
+            if args.get("--graph").is_some() {
+                return Err(CliError::Usage(
+                    "--graph can't be used with --witness".to_string(),
+                ));
+            }
+        }
+        None => {
+            args.positional(1, 1)?;
+            args.required("--graph")?;
+        }
+    }
+    let r1cs_path = args.required("--r1cs")?;
+    let pkey_path = args.required("--pkey")?;
+    let reduction = args.reduction()?;
+    let missing_inputs = args.missing_inputs()?;
+    let curve = args.curve()?;
+
+    let pkey_data = read_input(pkey_path)?;
+    if pkey_data.starts_with(zkey::MAGIC)
+        && args.get("--reduction").is_some()
+        && reduction != Reduction::Circom
+    {
+        return Err(CliError::Usage(
+            "a snarkjs zkey is always proven with --reduction circom".to_string(),
+        ));
+    }
+    let source = match args.get("--witness") {
+        Some(path) => WitnessSource::Wtns(read_input(path)?),
+        None => WitnessSource::Inputs {
+            inputs_data: read_input_string(&args.positional(1, 1)?[0])?,
+            graph_data: read_input(args.required("--graph")?)?,
+        },
+    };
+    let r1cs_data = read_input(r1cs_path)?;
+    let options = ProverOptions {
+        symbols: match args.get("--sym") {
+            Some(path) => Some(SymbolTable::from_sym(&read_input_string(path)?)?),
+            None => None,
+        },
+        missing_inputs,
+    };
+    with_curve!(
+        curve,
+        prove_with(args, &source, &r1cs_data, &pkey_data, reduction, options)
+    )
+}
+
+/// The proof and public inputs, and the public inputs by name with a symbol table
+type ProofWithSignals<E> = (
+    ProofWithInputs<E>,
+    Option<PublicSignals<<E as Pairing>::ScalarField>>,
+);
+
+struct ProverOptions {
+    symbols: Option<SymbolTable>,
+    missing_inputs: MissingInputs,
+}
+
+fn prove_source<E: CircomCurve, QAP: R1CSToQAP>(
+    prover: impl FnOnce(Option<&[u8]>) -> ark_circom_witnesscalc::Result<Prover<E, QAP>>,
+    source: &WitnessSource,
+    options: ProverOptions,
+) -> ark_circom_witnesscalc::Result<ProofWithSignals<E>> {
+    let graph_data = match source {
+        WitnessSource::Inputs { graph_data, .. } => Some(&graph_data[..]),
+        WitnessSource::Wtns(_) => None,
+    };
+    let mut prover = prover(graph_data)?;
+    if let Some(symbols) = options.symbols {
+        prover = prover.with_symbols(symbols)?;
+    }
+    if options.missing_inputs == MissingInputs::Zero {
+        prover = prover.allow_missing_inputs();
+    }
+    let (proof, public_inputs) = match source {
+        WitnessSource::Inputs { inputs_data, .. } => prover.prove(inputs_data)?,
+        WitnessSource::Wtns(wtns_data) => {
+            prover.prove_witness(witness_from_wtns(Cursor::new(wtns_data))?)?
+        }
+    };
+    let signals = match prover.symbols() {
+        Some(_) => Some(prover.public_signals(&public_inputs)?),
+        None => None,
+    };
+    Ok(((proof, public_inputs), signals))
+}
+
+fn prove_with<E: CircomCurve>(
+    args: &Args,
+    source: &WitnessSource,
+    r1cs_data: &[u8],
+    pkey_data: &[u8],
+    reduction: Reduction,
+    options: ProverOptions,
+) -> CliResult {
+    // A snarkjs zkey can be used in place of the arkworks proving key, and is
+    // always proven with the circom reduction
+    let ((proof, public_inputs), signals) = if pkey_data.starts_with(zkey::MAGIC) {
+        prove_source::<E, CircomReduction>(
+            |graph_data| match graph_data {
+                Some(graph_data) => Prover::from_zkey(pkey_data, graph_data, r1cs_data),
+                None => Prover::from_zkey_without_graph(pkey_data, r1cs_data),
+            },
+            source,
+            options,
+        )?
+    } else {
+        match reduction {
+            Reduction::Libsnark => prove_source::<E, LibsnarkReduction>(
+                |g| new_prover(pkey_data, g, r1cs_data),
+                source,
+                options,
+            )?,
+            Reduction::Circom => prove_source::<E, CircomReduction>(
+                |g| new_prover(pkey_data, g, r1cs_data),
+                source,
+                options,
+            )?,
+        }
+    };
+
+    let outputs = ["--proof", "--proof-bin", "--snarkjs-proof", "--public"];
+    let proof_path = match args.get("--proof") {
+        None if outputs.iter().all(|o| args.get(o).is_none()) => Some("-"),
+        path => path,
+    };
+    if let Some(path) = proof_path {
+        let json = match &signals {
+            Some(signals) => proof_to_json_with_signals(&proof, signals)?,
+            None => proof_to_json(&proof, &public_inputs)?,
+        };
+        write_output(path, json.as_bytes())?;
+    }
+    if let Some(path) = args.get("--proof-bin") {
+        write_output(path, &serialize(&proof)?)?;
+    }
+    if let Some(path) = args.get("--snarkjs-proof") {
+        write_output(path, proof_to_snarkjs_json(&proof)?.as_bytes())?;
+    }
+    if let Some(path) = args.get("--public") {
+        write_output(path, public_inputs_to_json(&public_inputs)?.as_bytes())?;
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn new_prover<E: CircomCurve, QAP: R1CSToQAP>(
+    pkey_data: &[u8],
+    graph_data: Option<&[u8]>,
+    r1cs_data: &[u8],
+) -> ark_circom_witnesscalc::Result<Prover<E, QAP>> {
+    match graph_data {
+        Some(graph_data) => Prover::new(pkey_data, graph_data, r1cs_data),
+        None => Prover::without_graph(pkey_data, r1cs_data),
+    }
+}
+
+pub fn verify(args: &Args) -> CliResult {
+    let positional = args.positional(2, usize::MAX)?;
+    let proof_data = read_input(&positional[0])?;
+    let vk_data = read_input(&positional[1])?;
+    let public_json = args.get("--public").map(read_input_string).transpose()?;
+    let public_json = public_json.as_deref();
+
+    let valid = match as_json(&proof_data) {
+        Some(proof_json) => {
+            if positional.len() > 2 {
+                return Err(CliError::Usage(
+                    "the public inputs of a JSON proof are in the proof, or in --public"
+                        .to_string(),
+                ));
+            }
+            let vk_json = as_json(&vk_data).ok_or_else(|| {
+                CliError::Other("a JSON proof needs a JSON verifying key".to_string())
+            })?;
+            // The curve is taken from the proof, the verifying key must match it
+            with_curve!(
+                Curve::from_json(proof_json)?,
+                verify_proof_json_with_public(vk_json, proof_json, public_json)
+            )?
+        }
+        None => with_curve!(
+            args.curve()?,
+            verify_binary(&vk_data, &proof_data, &positional[2..], public_json)
+        )?,
+    };
+
+    println!("{}", valid);
+    if valid {
+        Ok(ExitCode::SUCCESS)
+    } else {
+        Ok(ExitCode::from(REJECTED))
+    }
+}
+
+fn verify_binary<E: CircomCurve>(
+    vk_data: &[u8],
+    proof_data: &[u8],
+    inputs: &[String],
+    public_json: Option<&str>,
+) -> CliResult<bool> {
+    let public_inputs = match public_json {
+        Some(_) if !inputs.is_empty() => {
+            return Err(CliError::Usage(
+                "give the public inputs as arguments or in --public, not both".to_string(),
+            ))
+        }
+        Some(json) => public_inputs_from_json(json)?,
+        None => inputs
+            .iter()
+            .map(|value| parse_field_element(value))
+            .collect::<Result<Vec<E::ScalarField>, _>>()?,
+    };
+    Ok(verify_proof::<E>(vk_data, proof_data, &public_inputs)?)
+}
+
+pub fn witness(args: &Args) -> CliResult {
+    let inputs_data = read_input_string(&args.positional(1, 1)?[0])?;
+    let graph_data = read_input(args.required("--graph")?)?;
+    let output = args.get("--output").unwrap_or("-");
+    // The format follows the output's extension unless given
+    let format = match args.get("--format") {
+        Some(format) => format,
+        None if output.ends_with(".wtns") => "wtns",
+        None => "json",
+    };
+    if format != "wtns" && format != "json" {
+        return Err(CliError::Usage(format!("unknown format '{}'", format)));
+    }
+    let missing = args.missing_inputs()?;
+    with_curve!(
+        args.curve()?,
+        witness_with(&inputs_data, &graph_data, missing, format, output)
+    )
+}
+
+fn witness_with<E: CircomCurve>(
+    inputs_data: &str,
+    graph_data: &[u8],
+    missing: MissingInputs,
+    format: &str,
+    output: &str,
+) -> CliResult {
+    let witness = calc_witness2_with::<E::ScalarField>(inputs_data, graph_data, missing)?;
+    let data = match format {
+        "wtns" => witness_to_wtns(&witness),
+        _ => witness_to_json(&witness)?.into_bytes(),
+    };
+    write_output(output, &data)?;
+    Ok(ExitCode::SUCCESS)
+}
+
+pub fn check(args: &Args) -> CliResult {
+    let inputs_data = read_input_string(&args.positional(1, 1)?[0])?;
+    let graph_data = read_input(args.required("--graph")?)?;
+    let r1cs_data = read_input(args.required("--r1cs")?)?;
+    let symbols = match args.get("--sym") {
+        Some(path) => Some(SymbolTable::from_sym(&read_input_string(path)?)?),
+        None => None,
+    };
+    let missing = args.missing_inputs()?;
+    with_curve!(
+        args.curve()?,
+        check_with(
+            &inputs_data,
+            &graph_data,
+            missing,
+            &r1cs_data,
+            symbols.as_ref()
+        )
+    )
+}
+
+fn check_with<E: CircomCurve>(
+    inputs_data: &str,
+    graph_data: &[u8],
+    missing: MissingInputs,
+    r1cs_data: &[u8],
+    symbols: Option<&SymbolTable>,
+) -> CliResult {
+    let circom = CircomCircuit::<E::ScalarField> {
+        r1cs: R1CSFile::new(Cursor::new(r1cs_data))?.try_into()?,
+        witness: Some(calc_witness2_with(inputs_data, graph_data, missing)?),
+    };
+
+    let unsatisfied = circom.check_witness().map_err(Error::from)?;
+    if unsatisfied.is_empty() {
+        println!("true");
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    for constraint in &unsatisfied {
+        match symbols {
+            Some(symbols) => eprint!("{}", constraint.display_with(symbols)),
+            None => eprint!("{}", constraint),
+        }
+    }
+    eprintln!(
+        "{} of {} constraints not satisfied",
+        unsatisfied.len(),
+        circom.r1cs.constraints.len()
+    );
+    Ok(ExitCode::from(REJECTED))
+}
+
+pub fn inspect(args: &Args) -> CliResult {
+    let data = read_input(&args.positional(1, 1)?[0])?;
+    print!("{}", describe(&data)?);
+    Ok(ExitCode::SUCCESS)
+}
+
+/// What kind of file `data` is, and a summary of it
+fn describe(data: &[u8]) -> CliResult<String> {
+    if data.starts_with(b"r1cs") {
+        detect_curve(|curve| with_curve!(curve, describe_r1cs(data)))
+    } else if data.starts_with(zkey::MAGIC) {
+        detect_curve(|curve| with_curve!(curve, describe_zkey(data)))
+    } else if let Some(json) = as_json(data) {
+        describe_json(json)
+    } else {
+        describe_graph(data)
+    }
+}
+
+/// The first curve the file can be read with, or the error for BN254
+fn detect_curve(describe: impl Fn(Curve) -> CliResult<String>) -> CliResult<String> {
+    let mut first_error = None;
+    for curve in Curve::all() {
+        match describe(curve) {
+            Ok(description) => return Ok(description),
+            Err(e) => {
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+    Err(first_error.expect("at least one curve is supported"))
+}
+
+fn describe_r1cs<E: CircomCurve>(data: &[u8]) -> CliResult<String> {
+    let file = R1CSFile::<E::ScalarField>::new(Cursor::new(data))?;
+    let header = &file.header;
+    Ok(format!(
+        "r1cs version {}\ncurve: {}\nwires: {}\npublic outputs: {}\npublic inputs: {}\nprivate inputs: {}\nlabels: {}\nconstraints: {}\ncustom gates: {}\n",
+        file.version,
+        E::NAME,
+        header.n_wires,
+        header.n_pub_out,
+        header.n_pub_in,
+        header.n_prv_in,
+        header.n_labels,
+        header.n_constraints,
+        file.custom_gates_used.len(),
+    ))
+}
+
+fn describe_zkey<E: CircomCurve>(data: &[u8]) -> CliResult<String> {
+    let zkey = ZKey::<E>::new(Cursor::new(data))?;
+    Ok(format!(
+        "zkey, groth16\ncurve: {}\nvariables: {}\npublic inputs: {}\nconstraints: {}\n",
+        E::NAME,
+        zkey.r1cs.num_variables,
+        zkey.proving_key.vk.gamma_abc_g1.len().saturating_sub(1),
+        zkey.r1cs.constraints.len(),
+    ))
+}
+
+fn describe_json(json: &str) -> CliResult<String> {
+    let value: serde_json::Value = serde_json::from_str(json).map_err(Error::from)?;
+    let curve = value.get("curve").and_then(|c| c.as_str()).unwrap_or("?");
+    let len = |key: &str| value.get(key).and_then(|v| v.as_array()).map(Vec::len);
+    if let Some(inputs) = value.as_array() {
+        Ok(format!(
+            "snarkjs public.json\npublic inputs: {}\n",
+            inputs.len()
+        ))
+    } else if value.get("pi_a").is_some() {
+        Ok(format!("snarkjs proof\ncurve: {}\n", curve))
+    } else if let Some(inputs) = len("inputs") {
+        Ok(format!(
+            "proof\ncurve: {}\npublic inputs: {}\n",
+            curve, inputs
+        ))
+    } else if let Some(ic) = len("IC") {
+        let format = match len("vk_alpha_1") {
+            Some(3) => "snarkjs verifying key",
+            _ => "verifying key",
+        };
+        Ok(format!(
+            "{}\ncurve: {}\npublic inputs: {}\n",
+            format,
+            curve,
+            ic.saturating_sub(1)
+        ))
+    } else {
+        Err(CliError::Other(
+            "unrecognised JSON, expected a proof, verifying key or public.json".to_string(),
+        ))
+    }
+}
+
+fn describe_graph(data: &[u8]) -> CliResult<String> {
+    let (_, signals, inputs) =
+        circom_witnesscalc::storage::proto_deserializer::deserialize_witnesscalc_graph_from_bytes(
+            data,
+        )
+        .map_err(|_| {
+            CliError::Other(
+                "unrecognised file, expected an r1cs, zkey, witness graph or JSON".to_string(),
+            )
+        })?;
+    let mut inputs: Vec<_> = inputs.into_iter().collect();
+    inputs.sort_by_key(|(_, (offset, _))| *offset);
+
+    let mut description = format!("witness graph\nsignals: {}\ninputs:\n", signals.len());
+    for (name, (offset, len)) in inputs {
+        description.push_str(&format!("  {} at {}, length {}\n", name, offset, len));
+    }
+    Ok(description)
+}
+
+pub fn convert(args: &Args) -> CliResult {
+    let data = read_input(&args.positional(1, 1)?[0])?;
+    let to_snarkjs = match args.required("--to")? {
+        "ark" => false,
+        "snarkjs" => true,
+        to => {
+            return Err(CliError::Usage(format!(
+                "unknown format '{}', expected ark or snarkjs",
+                to
+            )))
+        }
+    };
+    let output = args.get("--output").unwrap_or("-");
+    match as_json(&data) {
+        Some(json) => with_curve!(
+            Curve::from_json(json)?,
+            convert_json(args, json, to_snarkjs, output)
+        ),
+        None => with_curve!(args.curve()?, convert_binary_vk(&data, to_snarkjs, output)),
+    }
+}
+
+fn convert_json<E: CircomCurve>(
+    args: &Args,
+    json: &str,
+    to_snarkjs: bool,
+    output: &str,
+) -> CliResult {
+    let value: serde_json::Value = serde_json::from_str(json).map_err(Error::from)?;
+    if value.get("vk_alpha_1").is_some() {
+        let vk = verifying_key_from_json::<E>(json)?;
+        return write_verifying_key(&vk, to_snarkjs, output);
+    }
+
+    // --public is read for snarkjs proofs, and written for proofs converted to snarkjs
+    let from_snarkjs = is_snarkjs_proof(json)?;
+    let public_json = match from_snarkjs {
+        true => args.get("--public").map(read_input_string).transpose()?,
+        false => None,
+    };
+    let (proof, public_inputs) = proof_and_inputs_from_json::<E>(json, public_json.as_deref())?;
+    if !to_snarkjs {
+        write_output(output, proof_to_json(&proof, &public_inputs)?.as_bytes())?;
+        return Ok(ExitCode::SUCCESS);
+    }
+    write_output(output, proof_to_snarkjs_json(&proof)?.as_bytes())?;
+    if let Some(path) = args.get("--public").filter(|_| !from_snarkjs) {
+        write_output(path, public_inputs_to_json(&public_inputs)?.as_bytes())?;
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn convert_binary_vk<E: CircomCurve>(data: &[u8], to_snarkjs: bool, output: &str) -> CliResult {
+    let vk = VerifyingKey::<E>::deserialize_uncompressed(data).map_err(|source| Error::Key {
+        kind: "verifying key",
+        source,
+    })?;
+    write_verifying_key(&vk, to_snarkjs, output)
+}
+
+fn write_verifying_key<E: CircomCurve>(
+    vk: &VerifyingKey<E>,
+    to_snarkjs: bool,
+    output: &str,
+) -> CliResult {
+    let json = match to_snarkjs {
+        true => verifying_key_to_snarkjs_json(vk)?,
+        false => verifying_key_to_json(vk)?,
+    };
+    write_output(output, json.as_bytes())?;
+    Ok(ExitCode::SUCCESS)
+}
+
+pub fn solidity(args: &Args) -> CliResult {
+    // Either verifying key format, the EVM precompiles only support BN254
+    let vk_json = read_input_string(&args.positional(1, 1)?[0])?;
+    let vk = verifying_key_from_json::<Bn254>(&vk_json)?;
+    let output = args.get("--output").unwrap_or("-");
+    write_output(output, solidity_verifier(&vk)?.as_bytes())?;
+    Ok(ExitCode::SUCCESS)
+}
+
+pub fn calldata(args: &Args) -> CliResult {
+    let proof_json = read_input_string(&args.positional(1, 1)?[0])?;
+    let public_json = args.get("--public").map(read_input_string).transpose()?;
+    let (proof, public_inputs) =
+        proof_and_inputs_from_json::<Bn254>(&proof_json, public_json.as_deref())?;
+
+    if let Some(path) = args.get("--eip197") {
+        write_output(path, &proof_to_eip197(&proof))?;
+    }
+
+    let calldata = format!(
+        "0x{}\n",
+        hex::encode(proof_to_calldata(&proof, &public_inputs))
+    );
+    write_output(args.get("--output").unwrap_or("-"), calldata.as_bytes())?;
+    Ok(ExitCode::SUCCESS)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::COMMANDS;
+
+    /// A directory of its own for each test, holding the example circuits,
+    /// removed when dropped even if the test fails
+    fn test_dir() -> TempDir {
+        let dir = tempfile::Builder::new()
+            .prefix("ark-cwc-")
+            .tempdir()
+            .unwrap();
+        let files: [(&str, &[u8]); 5] = [
+            (
+                "multiply.r1cs",
+                include_bytes!("../../../example/multiply.r1cs"),
+            ),
+            (
+                "multiply.graph",
+                include_bytes!("../../../example/multiply.graph"),
+            ),
+            (
+                "multiply.input.json",
+                include_bytes!("../../../example/multiply.input.json"),
+            ),
+            (
+                "multiplier.zkey",
+                include_bytes!("../../../example/snarkjs/multiplier.zkey"),
+            ),
+            (
+                "multiplier.r1cs",
+                include_bytes!("../../../example/snarkjs/multiplier.r1cs"),
+            ),
+        ];
+        for (file, data) in files {
+            std::fs::write(dir.path().join(file), data).unwrap();
+        }
+        dir
+    }
+
+    /// Run a command as `main` does. `{}` in an argument is replaced by the
+    /// test directory.
+    fn run(dir: &Path, name: &str, args: &[&str]) -> CliResult {
+        let command = COMMANDS.iter().find(|c| c.name == name).unwrap();
+        let dir = dir.to_str().unwrap();
+        let args: Vec<String> = args.iter().map(|a| a.replace("{}", dir)).collect();
+        (command.run)(&Args::parse(&args, command.options)?)
+    }
+
+    /// The exit code `main` would return for the command
+    fn exit_code(dir: &Path, name: &str, args: &[&str]) -> ExitCode {
+        run(dir, name, args).unwrap_or_else(|e| e.exit_code())
+    }
+
+    fn read_string(dir: &Path, file: &str) -> String {
+        std::fs::read_to_string(dir.join(file)).unwrap()
+    }
+
+    /// Make keys for the example circuit and prove it, writing `pkey`, `vk`,
+    /// `vk.json`, `proof.json` and `proof.bin` to the test directory
+    fn setup_and_prove(dir: &Path) {
+        let code = run(
+            dir,
+            "setup",
+            &[
+                "{}/multiply.r1cs",
+                "--pkey",
+                "{}/pkey",
+                "--vk",
+                "{}/vk",
+                "--vk-json",
+                "{}/vk.json",
+            ],
+        )
+        .unwrap();
+        assert_eq!(code, ExitCode::SUCCESS);
+        let code = run(
+            dir,
+            "prove",
+            &[
+                "{}/multiply.input.json",
+                "--graph",
+                "{}/multiply.graph",
+                "--r1cs",
+                "{}/multiply.r1cs",
+                "--pkey",
+                "{}/pkey",
+                "--proof",
+                "{}/proof.json",
+                "--proof-bin",
+                "{}/proof.bin",
+            ],
+        )
+        .unwrap();
+        assert_eq!(code, ExitCode::SUCCESS);
+    }
+
+    #[test]
+    fn prove_then_verify() {
+        let tmp = test_dir();
+        let dir = tmp.path();
+        setup_and_prove(dir);
+
+        let code = run(dir, "verify", &["{}/proof.json", "{}/vk.json"]).unwrap();
+        assert_eq!(code, ExitCode::SUCCESS);
+        let code = run(dir, "verify", &["{}/proof.bin", "{}/vk", "110", "11"]).unwrap();
+        assert_eq!(code, ExitCode::SUCCESS);
+
+        // The right proof for different public inputs
+        let code = run(dir, "verify", &["{}/proof.bin", "{}/vk", "10", "110"]).unwrap();
+        assert_eq!(code, ExitCode::from(REJECTED));
+    }
+
+    #[test]
+    fn check_rejects_unsatisfied_witnesses() {
+        let tmp = test_dir();
+        let dir = tmp.path();
+        let args = |r1cs: &'static str| {
+            [
+                "{}/multiply.input.json",
+                "--graph",
+                "{}/multiply.graph",
+                "--r1cs",
+                r1cs,
+            ]
+        };
+        assert_eq!(
+            exit_code(dir, "check", &args("{}/multiply.r1cs")),
+            ExitCode::SUCCESS
+        );
+
+        // The constraint's B coefficient doubled, so -a·2b != -c
+        let mut r1cs = std::fs::read(dir.join("multiply.r1cs")).unwrap();
+        assert_eq!(r1cs[0x48], 1);
+        r1cs[0x48] = 2;
+        std::fs::write(dir.join("doubled.r1cs"), r1cs).unwrap();
+        assert_eq!(
+            exit_code(dir, "check", &args("{}/doubled.r1cs")),
+            ExitCode::from(REJECTED)
+        );
+    }
+
+    #[test]
+    fn witness_as_wtns_or_json() {
+        let tmp = test_dir();
+        let dir = tmp.path();
+        let witness = |extra: &[&str]| {
+            let mut args = vec!["{}/multiply.input.json", "--graph", "{}/multiply.graph"];
+            args.extend(extra);
+            exit_code(dir, "witness", &args)
+        };
+        let expected = [1u64, 110, 11, 10].map(ark_bn254::Fr::from);
+
+        // The format follows the extension, or --format
+        for (args, file) in [
+            (&["-o", "{}/witness.wtns"][..], "witness.wtns"),
+            (
+                &["--format", "wtns", "-o", "{}/witness.bin"][..],
+                "witness.bin",
+            ),
+        ] {
+            assert_eq!(witness(args), ExitCode::SUCCESS);
+            let data = std::fs::read(dir.join(file)).unwrap();
+            let wtns: Vec<ark_bn254::Fr> = witness_from_wtns(Cursor::new(data)).unwrap();
+            assert_eq!(wtns, expected);
+        }
+
+        assert_eq!(
+            witness(&["--format", "json", "-o", "{}/witness.json"]),
+            ExitCode::SUCCESS
+        );
+        let json: Vec<String> = serde_json::from_str(&read_string(dir, "witness.json")).unwrap();
+        assert_eq!(json, ["1", "110", "11", "10"]);
+
+        assert_eq!(witness(&["--format", "csv"]), ExitCode::from(crate::USAGE));
+    }
+
+    #[test]
+    fn convert_inspect_solidity_and_calldata() {
+        use ark_circom_witnesscalc::{
+            proof_from_json, proof_from_snarkjs_json, verifying_key_from_snarkjs_json,
+        };
+
+        let tmp = test_dir();
+        let dir = tmp.path();
+        setup_and_prove(dir);
+        let (proof, inputs) = proof_from_json::<Bn254>(&read_string(dir, "proof.json")).unwrap();
+
+        // A proof to snarkjs and back
+        let code = exit_code(
+            dir,
+            "convert",
+            &[
+                "{}/proof.json",
+                "--to",
+                "snarkjs",
+                "-o",
+                "{}/snarkjs-proof.json",
+                "--public",
+                "{}/public.json",
+            ],
+        );
+        assert_eq!(code, ExitCode::SUCCESS);
+        let snarkjs = proof_from_snarkjs_json::<Bn254>(&read_string(dir, "snarkjs-proof.json"));
+        assert!(snarkjs.unwrap() == proof);
+        let public: Vec<ark_bn254::Fr> =
+            public_inputs_from_json(&read_string(dir, "public.json")).unwrap();
+        assert_eq!(public, inputs);
+        let code = exit_code(
+            dir,
+            "convert",
+            &[
+                "{}/snarkjs-proof.json",
+                "--to",
+                "ark",
+                "--public",
+                "{}/public.json",
+                "-o",
+                "{}/ark-proof.json",
+            ],
+        );
+        assert_eq!(code, ExitCode::SUCCESS);
+        let (ark, ark_inputs) =
+            proof_from_json::<Bn254>(&read_string(dir, "ark-proof.json")).unwrap();
+        assert!(ark == proof);
+        assert_eq!(ark_inputs, inputs);
+
+        // The verifying key, from JSON or binary
+        for (vk, out) in [
+            ("{}/vk.json", "{}/vk-from-json.json"),
+            ("{}/vk", "{}/vk-from-bin.json"),
+        ] {
+            let code = exit_code(dir, "convert", &[vk, "--to", "snarkjs", "-o", out]);
+            assert_eq!(code, ExitCode::SUCCESS);
+        }
+        let vk = verifying_key_from_json::<Bn254>(&read_string(dir, "vk.json")).unwrap();
+        for file in ["vk-from-json.json", "vk-from-bin.json"] {
+            let converted = verifying_key_from_snarkjs_json::<Bn254>(&read_string(dir, file));
+            assert!(converted.unwrap() == vk);
+        }
+        assert_eq!(
+            exit_code(dir, "convert", &["{}/vk.json", "--to", "foo"]),
+            ExitCode::from(crate::USAGE)
+        );
+
+        for (file, kind) in [
+            ("multiply.r1cs", "r1cs version 1"),
+            ("multiply.graph", "witness graph"),
+            ("multiplier.zkey", "zkey, groth16"),
+            ("proof.json", "proof"),
+            ("vk.json", "verifying key"),
+            ("vk-from-json.json", "snarkjs verifying key"),
+            ("public.json", "snarkjs public.json"),
+        ] {
+            let path = format!("{{}}/{}", file);
+            assert_eq!(exit_code(dir, "inspect", &[&path]), ExitCode::SUCCESS);
+            let description = describe(&std::fs::read(dir.join(file)).unwrap()).unwrap();
+            assert_eq!(description.lines().next(), Some(kind), "{}", file);
+        }
+
+        let code = exit_code(dir, "solidity", &["{}/vk.json", "-o", "{}/Verifier.sol"]);
+        assert_eq!(code, ExitCode::SUCCESS);
+        assert_eq!(
+            read_string(dir, "Verifier.sol"),
+            solidity_verifier(&vk).unwrap()
+        );
+
+        let code = exit_code(
+            dir,
+            "calldata",
+            &[
+                "{}/proof.json",
+                "--eip197",
+                "{}/proof.eip197",
+                "-o",
+                "{}/calldata",
+            ],
+        );
+        assert_eq!(code, ExitCode::SUCCESS);
+        assert_eq!(
+            std::fs::read(dir.join("proof.eip197")).unwrap(),
+            proof_to_eip197(&proof)
+        );
+        let calldata = read_string(dir, "calldata");
+        let calldata = hex::decode(calldata.trim().strip_prefix("0x").unwrap()).unwrap();
+        assert_eq!(calldata, proof_to_calldata(&proof, &inputs));
+    }
+
+    #[test]
+    fn missing_files_and_bad_usage_exit_codes() {
+        let failed = ExitCode::from(crate::FAILED);
+        let usage = ExitCode::from(crate::USAGE);
+        let cases: [(&str, &[&str], ExitCode); 12] = [
+            ("setup", &["{}/missing.r1cs", "--pkey", "{}/pkey"], failed),
+            ("setup", &["{}/multiply.r1cs"], usage),
+            (
+                "prove",
+                &[
+                    "{}/multiply.input.json",
+                    "--graph",
+                    "{}/multiply.graph",
+                    "--r1cs",
+                    "{}/multiply.r1cs",
+                    "--pkey",
+                    "{}/missing",
+                ],
+                failed,
+            ),
+            ("verify", &["{}/missing.json", "{}/missing.json"], failed),
+            (
+                "witness",
+                &["{}/multiply.input.json", "--graph", "{}/missing.graph"],
+                failed,
+            ),
+            (
+                "check",
+                &[
+                    "{}/multiply.input.json",
+                    "--graph",
+                    "{}/multiply.graph",
+                    "--r1cs",
+                    "{}/missing.r1cs",
+                ],
+                failed,
+            ),
+            (
+                "check",
+                &["{}/multiply.input.json", "--r1cs", "{}/multiply.r1cs"],
+                usage,
+            ),
+            ("inspect", &["{}/missing"], failed),
+            ("inspect", &[], usage),
+            ("convert", &["{}/missing.json", "--to", "snarkjs"], failed),
+            ("solidity", &["{}/missing.json"], failed),
+            ("calldata", &["{}/missing.json"], failed),
+        ];
+        let tmp = test_dir();
+        for (name, args, expected) in cases {
+            assert_eq!(
+                exit_code(tmp.path(), name, args),
+                expected,
+                "{} {:?}",
+                name,
+                args
+            );
+        }
+    }
+
+    #[test]
+    fn zkeys_are_only_proven_with_circom() {
+        let tmp = test_dir();
+        let dir = tmp.path();
+        // The witness and r1cs don't exist, the reduction is checked against
+        // the zkey before they're read
+        let args = [
+            "--witness",
+            "{}/missing.wtns",
+            "--r1cs",
+            "{}/missing.r1cs",
+            "--pkey",
+            "{}/multiplier.zkey",
+            "--reduction",
+            "libsnark",
+        ];
+        match run(dir, "prove", &args) {
+            Err(CliError::Usage(message)) => {
+                assert!(message.contains("--reduction circom"), "{}", message)
+            }
+            Err(e) => panic!("expected a usage error, got '{}'", e),
+            Ok(_) => panic!("expected a usage error"),
+        }
+    }
+}
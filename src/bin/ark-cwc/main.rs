@@ -0,0 +1,421 @@
+//! `ark-cwc`, one command-line tool for setup, proving and verification.
+//!
+//! Exit codes are the same for every command:
+//!  * 0 - success
+//!  * 1 - the proof was rejected, or the witness doesn't satisfy the constraints
+//!  * 2 - the command line is invalid
+//!  * 3 - any other error, e.g. a file is missing or malformed
+
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::process::ExitCode;
+
+use ark_circom_witnesscalc::{Curve, MissingInputs, Reduction};
+
+/// Call a function generic over the curve, with the curve chosen at runtime
+macro_rules! with_curve {
+    ($curve:expr, $func:ident($($arg:expr),* $(,)?) $(,)?) => {
+        match $curve {
+            ark_circom_witnesscalc::Curve::Bn254 => $func::<ark_bn254::Bn254>($($arg),*),
+            #[cfg(feature = "bls12-381")]
+            ark_circom_witnesscalc::Curve::Bls12_381 => {
+                $func::<ark_bls12_381::Bls12_381>($($arg),*)
+            }
+            #[cfg(feature = "bls12-377")]
+            ark_circom_witnesscalc::Curve::Bls12_377 => {
+                $func::<ark_bls12_377::Bls12_377>($($arg),*)
+            }
+        }
+    };
+}
+
+mod commands;
+
+pub const REJECTED: u8 = 1;
+const USAGE: u8 = 2;
+const FAILED: u8 = 3;
+
+#[derive(Debug)]
+pub enum CliError {
+    Usage(String),
+    Io { path: String, source: io::Error },
+    Failed(ark_circom_witnesscalc::Error),
+    Other(String),
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::Usage(message) => write!(f, "{}", message),
+            CliError::Io { path, source } => write!(f, "{}: {}", path, source),
+            CliError::Failed(e) => write!(f, "{}", e),
+            CliError::Other(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl CliError {
+    /// What `main` exits with for this error
+    fn exit_code(&self) -> ExitCode {
+        match self {
+            CliError::Usage(_) => ExitCode::from(USAGE),
+            _ => ExitCode::from(FAILED),
+        }
+    }
+}
+
+impl From<ark_circom_witnesscalc::Error> for CliError {
+    fn from(e: ark_circom_witnesscalc::Error) -> Self {
+        CliError::Failed(e)
+    }
+}
+
+pub type CliResult<T = ExitCode> = Result<T, CliError>;
+
+/// The arguments of one command, positional arguments in order and options
+/// by name. Options always take a value, `--name value` or `--name=value`.
+pub struct Args {
+    positional: Vec<String>,
+    options: HashMap<String, String>,
+}
+
+impl Args {
+    fn parse(args: &[String], allowed: &[&str]) -> CliResult<Args> {
+        let mut positional = Vec::new();
+        let mut options = HashMap::new();
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            // `-` on its own is stdin or stdout
+            if arg == "-" || !arg.starts_with('-') {
+                positional.push(arg.clone());
+                continue;
+            }
+            let (name, value) = match arg.split_once('=') {
+                Some((name, value)) => (name, Some(value.to_string())),
+                None => (arg.as_str(), None),
+            };
+            let name = match name {
+                "-o" => "--output",
+                name => name,
+            };
+            if !allowed.contains(&name) {
+                return Err(CliError::Usage(format!("unknown option '{}'", arg)));
+            }
+            let value = match value {
+                Some(value) => value,
+                None => iter
+                    .next()
+                    .cloned()
+                    .ok_or_else(|| CliError::Usage(format!("option '{}' needs a value", name)))?,
+            };
+            if options.insert(name.to_string(), value).is_some() {
+                return Err(CliError::Usage(format!("option '{}' given twice", name)));
+            }
+        }
+        Ok(Args {
+            positional,
+            options,
+        })
+    }
+
+    /// Between `min` and `max` positional arguments
+    pub fn positional(&self, min: usize, max: usize) -> CliResult<&[String]> {
+        let n = self.positional.len();
+        if n < min {
+            return Err(CliError::Usage("missing arguments".to_string()));
+        }
+        if n > max {
+            return Err(CliError::Usage(format!(
+                "unexpected argument '{}'",
+                self.positional[max]
+            )));
+        }
+        Ok(&self.positional)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.options.get(name).map(String::as_str)
+    }
+
+    pub fn required(&self, name: &str) -> CliResult<&str> {
+        self.get(name)
+            .ok_or_else(|| CliError::Usage(format!("option '{}' is required", name)))
+    }
+
+    /// `--curve`, defaulting to BN254
+    pub fn curve(&self) -> CliResult<Curve> {
+        self.get("--curve")
+            .unwrap_or("bn128")
+            .parse()
+            .map_err(|e: ark_circom_witnesscalc::Error| CliError::Usage(e.to_string()))
+    }
+
+    /// `--reduction`, defaulting to libsnark
+    pub fn reduction(&self) -> CliResult<Reduction> {
+        match self.get("--reduction") {
+            None => Ok(Reduction::default()),
+            Some(name) => name
+                .parse()
+                .map_err(|e: ark_circom_witnesscalc::Error| CliError::Usage(e.to_string())),
+        }
+    }
+
+    /// `--missing-inputs`, by default an error
+    pub fn missing_inputs(&self) -> CliResult<MissingInputs> {
+        match self.get("--missing-inputs") {
+            None | Some("error") => Ok(MissingInputs::Error),
+            Some("zero") => Ok(MissingInputs::Zero),
+            Some(other) => Err(CliError::Usage(format!(
+                "--missing-inputs must be 'error' or 'zero', not '{}'",
+                other
+            ))),
+        }
+    }
+}
+
+/// Read a file, or stdin for `-`
+pub fn read_input(path: &str) -> CliResult<Vec<u8>> {
+    let result = match path {
+        "-" => {
+            let mut data = Vec::new();
+            io::stdin().read_to_end(&mut data).map(|_| data)
+        }
+        path => std::fs::read(path),
+    };
+    result.map_err(|source| CliError::Io {
+        path: path.to_string(),
+        source,
+    })
+}
+
+pub fn read_input_string(path: &str) -> CliResult<String> {
+    String::from_utf8(read_input(path)?).map_err(|_| CliError::Io {
+        path: path.to_string(),
+        source: io::Error::new(io::ErrorKind::InvalidData, "not valid UTF-8"),
+    })
+}
+
+/// Write a file, or stdout for `-`
+pub fn write_output(path: &str, data: &[u8]) -> CliResult<()> {
+    let result = match path {
+        "-" => io::stdout().write_all(data),
+        path => std::fs::write(path, data),
+    };
+    result.map_err(|source| CliError::Io {
+        path: path.to_string(),
+        source,
+    })
+}
+
+pub struct Command {
+    name: &'static str,
+    usage: &'static str,
+    about: &'static str,
+    options: &'static [&'static str],
+    run: fn(&Args) -> CliResult,
+}
+
+const CURVE: &str = "--curve";
+const REDUCTION: &str = "--reduction";
+const OUTPUT: &str = "--output";
+const MISSING_INPUTS: &str = "--missing-inputs";
+
+const COMMANDS: &[Command] = &[
+    Command {
+        name: "setup",
+        usage: "setup <circuit.r1cs> [--pkey <out>] [--vk <out>] [--vk-json <out>] [--snarkjs-vk <out>] [--curve <name>] [--reduction libsnark|circom]",
+        about: "Generate a proving key and verifying key, writing any of them",
+        options: &["--pkey", "--vk", "--vk-json", "--snarkjs-vk", CURVE, REDUCTION],
+        run: commands::setup,
+    },
+    Command {
+        name: "prove",
+        usage: "prove (<input.json> --graph <circuit.graph> | --witness <witness.wtns>) --r1cs <circuit.r1cs> --pkey <pkey|zkey> [--sym <circuit.sym>] [--missing-inputs error|zero] [--proof <out>] [--proof-bin <out>] [--snarkjs-proof <out>] [--public <out>] [--curve <name>] [--reduction libsnark|circom]",
+        about: "Prove with a proving key from setup or a snarkjs zkey, computing the witness from the inputs and graph or reading it from a .wtns file. With --sym the JSON proof names each public input. The JSON proof goes to stdout unless another output is given",
+        options: &[
+            "--graph",
+            "--witness",
+            "--sym",
+            MISSING_INPUTS,
+            "--r1cs",
+            "--pkey",
+            "--proof",
+            "--proof-bin",
+            "--snarkjs-proof",
+            "--public",
+            CURVE,
+            REDUCTION,
+        ],
+        run: commands::prove,
+    },
+    Command {
+        name: "verify",
+        usage: "verify <proof> <vk> [public inputs ...] [--public <public.json>] [--curve <name>]",
+        about: "Verify a JSON proof with a JSON verifying key, or a binary proof with a binary verifying key",
+        options: &["--public", CURVE],
+        run: commands::verify,
+    },
+    Command {
+        name: "witness",
+        usage: "witness <input.json> --graph <circuit.graph> [--missing-inputs error|zero] [--format wtns|json] [-o <out>] [--curve <name>]",
+        about: "Compute the witness, written as a snarkjs .wtns file or a JSON list of decimal strings. The format defaults to wtns for outputs ending in .wtns, otherwise json",
+        options: &["--graph", MISSING_INPUTS, "--format", OUTPUT, CURVE],
+        run: commands::witness,
+    },
+    Command {
+        name: "check",
+        usage: "check <input.json> --graph <circuit.graph> --r1cs <circuit.r1cs> [--sym <circuit.sym>] [--missing-inputs error|zero] [--curve <name>]",
+        about: "Compute the witness and print every constraint it doesn't satisfy, naming wires with the signals from --sym",
+        options: &["--graph", "--r1cs", "--sym", MISSING_INPUTS, CURVE],
+        run: commands::check,
+    },
+    Command {
+        name: "inspect",
+        usage: "inspect <file>",
+        about: "Describe an r1cs, zkey, graph, or JSON proof, verifying key or public inputs",
+        options: &[],
+        run: commands::inspect,
+    },
+    Command {
+        name: "convert",
+        usage: "convert <proof|vk> --to ark|snarkjs [-o <out>] [--public <public.json>] [--curve <name>]",
+        about: "Convert a proof or verifying key between this crate's JSON and snarkjs' JSON. --public is read for snarkjs proofs and written for proofs converted to snarkjs. Binary verifying keys are converted to JSON",
+        options: &["--to", OUTPUT, "--public", CURVE],
+        run: commands::convert,
+    },
+    Command {
+        name: "solidity",
+        usage: "solidity <vk.json> [-o <out.sol>]",
+        about: "Write a Solidity verifier contract for a BN254 verifying key",
+        options: &[OUTPUT],
+        run: commands::solidity,
+    },
+    Command {
+        name: "calldata",
+        usage: "calldata <proof.json> [--public <public.json>] [--eip197 <out>] [-o <out>]",
+        about: "Write the hex calldata calling verifyProof on the Solidity verifier, and optionally the 256 byte EIP-197 proof",
+        options: &["--public", "--eip197", OUTPUT],
+        run: commands::calldata,
+    },
+];
+
+fn print_help() {
+    println!("Usage: ark-cwc <command> [arguments]\n\nCommands:");
+    for command in COMMANDS {
+        println!("  {}\n      {}", command.usage, command.about);
+    }
+    println!("\nInput and output paths may be '-' for stdin or stdout.");
+    println!(
+        "Every input signal must be given, unless --missing-inputs zero sets missing ones to 0."
+    );
+    println!("Exit codes: 0 success, 1 proof rejected or constraints not satisfied, 2 invalid usage, 3 other errors.");
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let name = match args.first().map(String::as_str) {
+        None | Some("help" | "--help" | "-h") => {
+            print_help();
+            return ExitCode::SUCCESS;
+        }
+        Some(name) => name,
+    };
+    let Some(command) = COMMANDS.iter().find(|c| c.name == name) else {
+        eprintln!("error: unknown command '{}', see 'ark-cwc help'", name);
+        return ExitCode::from(USAGE);
+    };
+    if args[1..].iter().any(|a| a == "--help" || a == "-h") {
+        println!("Usage: ark-cwc {}\n\n{}", command.usage, command.about);
+        return ExitCode::SUCCESS;
+    }
+
+    let result = Args::parse(&args[1..], command.options).and_then(|args| (command.run)(&args));
+    match result {
+        Ok(code) => code,
+        Err(e) => {
+            match &e {
+                CliError::Usage(_) => eprintln!("error: {}\nUsage: ark-cwc {}", e, command.usage),
+                _ => eprintln!("error: {}", e),
+            }
+            e.exit_code()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str], allowed: &[&str]) -> CliResult<Args> {
+        let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+        Args::parse(&args, allowed)
+    }
+
+    fn usage_error(result: CliResult<Args>) -> String {
+        match result {
+            Err(CliError::Usage(message)) => message,
+            Err(e) => panic!("expected a usage error, got '{}'", e),
+            Ok(_) => panic!("expected a usage error"),
+        }
+    }
+
+    #[test]
+    fn parse_options_and_positionals() {
+        let args = parse(
+            &[
+                "in.json",
+                "--graph=c.graph",
+                "-",
+                "-o",
+                "out.wtns",
+                "--curve",
+                "bn128",
+            ],
+            &["--graph", OUTPUT, CURVE],
+        )
+        .unwrap();
+        assert_eq!(args.positional(2, 2).unwrap(), ["in.json", "-"]);
+        assert_eq!(args.get("--graph"), Some("c.graph"));
+        assert_eq!(args.get("--output"), Some("out.wtns"));
+        assert_eq!(args.get("--curve"), Some("bn128"));
+        assert!(matches!(args.positional(3, 3), Err(CliError::Usage(_))));
+        assert!(matches!(args.positional(0, 1), Err(CliError::Usage(_))));
+        assert!(matches!(args.required("--r1cs"), Err(CliError::Usage(_))));
+
+        // A value may itself contain '=' or start with '-'
+        let args = parse(&["--graph=a=b", "--public", "-"], &["--graph", "--public"]).unwrap();
+        assert_eq!(args.get("--graph"), Some("a=b"));
+        assert_eq!(args.get("--public"), Some("-"));
+    }
+
+    #[test]
+    fn parse_errors_are_usage_errors() {
+        let message = usage_error(parse(&["-o", "a", "--output=b"], &[OUTPUT]));
+        assert!(message.contains("given twice"), "{}", message);
+        let message = usage_error(parse(&["--pkey", "k"], &[OUTPUT]));
+        assert!(message.contains("unknown option '--pkey'"), "{}", message);
+        let message = usage_error(parse(&["in.json", "--graph"], &["--graph"]));
+        assert!(message.contains("needs a value"), "{}", message);
+
+        let args = parse(
+            &[
+                "--curve",
+                "foo",
+                "--reduction",
+                "foo",
+                "--missing-inputs",
+                "foo",
+            ],
+            &[CURVE, REDUCTION, MISSING_INPUTS],
+        )
+        .unwrap();
+        assert!(matches!(args.curve(), Err(CliError::Usage(_))));
+        assert!(matches!(args.reduction(), Err(CliError::Usage(_))));
+        assert!(matches!(args.missing_inputs(), Err(CliError::Usage(_))));
+
+        let args = parse(&[], &[CURVE, REDUCTION]).unwrap();
+        assert_eq!(args.curve().ok(), Some(Curve::Bn254));
+        assert_eq!(args.reduction().ok(), Some(Reduction::Libsnark));
+    }
+}
@@ -0,0 +1,179 @@
+//! Reading the iden3 binary container shared by `.r1cs`, `.zkey` and `.wtns`
+//! files: a magic number, a version, then a table of typed sections.
+//! Spec: <https://github.com/iden3/binfileutils>
+
+use ark_ff::{BigInteger, PrimeField};
+use byteorder::{LittleEndian, ReadBytesExt};
+
+use ark_std::io::{Read, Seek, SeekFrom};
+
+use std::collections::HashMap;
+
+use crate::error::{Error, Result};
+
+/// Wraps the underlying reader so every error records the byte offset
+/// where reading stopped, as the error variant for the file format
+pub(crate) struct OffsetReader<R> {
+    inner: R,
+    error: fn(u64, String) -> Error,
+}
+
+impl<R: Read + Seek> OffsetReader<R> {
+    pub(crate) fn new(inner: R, error: fn(u64, String) -> Error) -> Self {
+        OffsetReader { inner, error }
+    }
+
+    pub(crate) fn error(&mut self, message: impl Into<String>) -> Error {
+        let offset = self.inner.stream_position().unwrap_or_default();
+        (self.error)(offset, message.into())
+    }
+
+    pub(crate) fn check<T>(&mut self, result: std::io::Result<T>) -> Result<T> {
+        result.map_err(|e| self.error(e.to_string()))
+    }
+
+    pub(crate) fn read_u32(&mut self) -> Result<u32> {
+        let result = self.inner.read_u32::<LittleEndian>();
+        self.check(result)
+    }
+
+    pub(crate) fn read_u64(&mut self) -> Result<u64> {
+        let result = self.inner.read_u64::<LittleEndian>();
+        self.check(result)
+    }
+
+    pub(crate) fn read_exact(&mut self, buf: &mut [u8]) -> Result<()> {
+        let result = self.inner.read_exact(buf);
+        self.check(result)
+    }
+
+    /// Read a little-endian field element of `size` bytes, which must be
+    /// less than the modulus
+    pub(crate) fn read_field<F: PrimeField>(&mut self, size: u32) -> Result<F> {
+        let mut bytes = vec![0u8; size as usize];
+        self.read_exact(&mut bytes)?;
+        let value = F::from_le_bytes_mod_order(&bytes);
+        if le_bytes(value.into_bigint(), bytes.len()).as_deref() != Some(&bytes[..]) {
+            return Err(self.error("Field element is not less than the prime"));
+        }
+        Ok(value)
+    }
+
+    /// Read a prime of `size` bytes and check it is the modulus of `F`
+    pub(crate) fn read_prime<F: PrimeField>(&mut self, size: u32) -> Result<Vec<u8>> {
+        let mut prime = vec![0u8; size as usize];
+        self.read_exact(&mut prime)?;
+        if le_bytes(F::MODULUS, prime.len()).as_deref() != Some(&prime[..]) {
+            let mut be = prime.clone();
+            be.reverse();
+            return Err(self.error(format!(
+                "Prime 0x{} does not match the field modulus {}",
+                hex::encode(be),
+                F::MODULUS
+            )));
+        }
+        Ok(prime)
+    }
+
+    pub(crate) fn position(&mut self) -> Result<u64> {
+        let result = self.inner.stream_position();
+        self.check(result)
+    }
+
+    pub(crate) fn seek(&mut self, offset: u64) -> Result<()> {
+        let result = self.inner.seek(SeekFrom::Start(offset));
+        self.check(result).map(|_| ())
+    }
+
+    /// The length of the whole input, leaving the position unchanged
+    pub(crate) fn stream_len(&mut self) -> Result<u64> {
+        let position = self.position()?;
+        let result = self.inner.seek(SeekFrom::End(0));
+        let len = self.check(result)?;
+        self.seek(position)?;
+        Ok(len)
+    }
+}
+
+/// Little-endian bytes of `n` in exactly `size` bytes, or `None` if it
+/// doesn't fit
+pub(crate) fn le_bytes<B: BigInteger>(n: B, size: usize) -> Option<Vec<u8>> {
+    let mut bytes = n.to_bytes_le();
+    if bytes.len() > size {
+        if bytes[size..].iter().any(|b| *b != 0) {
+            return None;
+        }
+        bytes.truncate(size);
+    }
+    bytes.resize(size, 0);
+    Some(bytes)
+}
+
+/// Offset and size of each section, by section type
+pub(crate) struct Sections {
+    sections: HashMap<u32, (u64, u64)>,
+}
+
+impl Sections {
+    /// Check the magic number and version, then index the sections. Every
+    /// section must end within the input, so counts checked against a
+    /// section's size can't be larger than the file.
+    pub(crate) fn read<R: Read + Seek>(
+        reader: &mut OffsetReader<R>,
+        magic: &[u8; 4],
+        version: u32,
+    ) -> Result<Sections> {
+        let mut got = [0u8; 4];
+        reader.read_exact(&mut got)?;
+        if &got != magic {
+            return Err(reader.error("Invalid magic number"));
+        }
+
+        let got = reader.read_u32()?;
+        if got != version {
+            return Err(reader.error(format!("Unsupported version {}", got)));
+        }
+
+        let num_sections = reader.read_u32()?;
+        let len = reader.stream_len()?;
+
+        let mut sections = HashMap::new();
+        for _ in 0..num_sections {
+            let sec_type = reader.read_u32()?;
+            let sec_size = reader.read_u64()?;
+            let offset = reader.position()?;
+            sections.insert(sec_type, (offset, sec_size));
+            let end = offset
+                .checked_add(sec_size)
+                .ok_or_else(|| reader.error("Invalid section size"))?;
+            if end > len {
+                return Err(reader.error(format!(
+                    "Section {} ends at byte {}, past the end of the file at {}",
+                    sec_type, end, len
+                )));
+            }
+            reader.seek(end)?;
+        }
+        Ok(Sections { sections })
+    }
+
+    pub(crate) fn contains(&self, sec_type: u32) -> bool {
+        self.sections.contains_key(&sec_type)
+    }
+
+    /// Seek to the start of a section, returning its size
+    pub(crate) fn seek<R: Read + Seek>(
+        &self,
+        reader: &mut OffsetReader<R>,
+        sec_type: u32,
+        name: &str,
+    ) -> Result<u64> {
+        match self.sections.get(&sec_type) {
+            Some((offset, size)) => {
+                reader.seek(*offset)?;
+                Ok(*size)
+            }
+            None => Err(reader.error(format!("No {} section found", name))),
+        }
+    }
+}
@@ -8,10 +8,10 @@ use ark_relations::r1cs::{
 };
 
 use ark_ff::PrimeField;
+use std::fmt;
 
 use super::r1cs_reader::R1CS;
-
-use anyhow::Result;
+use super::sym::SymbolTable;
 
 #[derive(Clone, Debug)]
 pub struct CircomCircuit<F: PrimeField> {
@@ -19,70 +19,263 @@ pub struct CircomCircuit<F: PrimeField> {
     pub witness: Option<Vec<F>>,
 }
 
+/// Same as [`CircomCircuit`], but borrows the R1CS and witness so a cached
+/// constraint system can be proven against repeatedly without cloning either.
+#[derive(Clone, Debug)]
+pub struct CircomCircuitRef<'a, F: PrimeField> {
+    pub r1cs: &'a R1CS<F>,
+    pub witness: Option<&'a [F]>,
+}
+
 impl<F: PrimeField> CircomCircuit<F> {
     pub fn get_public_inputs(&self) -> Option<Vec<F>> {
-        match &self.witness {
-            None => None,
-            Some(w) => match &self.r1cs.wire_mapping {
-                None => Some(w[1..self.r1cs.num_inputs].to_vec()),
-                Some(m) => Some(m[1..self.r1cs.num_inputs].iter().map(|i| w[*i]).collect()),
-            },
+        public_inputs(&self.r1cs, self.witness.as_deref())
+    }
+
+    /// Check A·w ∗ B·w == C·w for every constraint, returning the ones which
+    /// fail. Errors if there is no witness, or it is too short for the R1CS.
+    pub fn check_witness(&self) -> Result<Vec<UnsatisfiedConstraint<F>>, SynthesisError> {
+        check_witness(&self.r1cs, self.witness.as_deref())
+    }
+}
+
+impl<F: PrimeField> CircomCircuitRef<'_, F> {
+    pub fn get_public_inputs(&self) -> Option<Vec<F>> {
+        public_inputs(self.r1cs, self.witness)
+    }
+
+    pub fn check_witness(&self) -> Result<Vec<UnsatisfiedConstraint<F>>, SynthesisError> {
+        check_witness(self.r1cs, self.witness)
+    }
+}
+
+fn public_inputs<F: PrimeField>(r1cs: &R1CS<F>, witness: Option<&[F]>) -> Option<Vec<F>> {
+    witness?.get(1..r1cs.num_inputs).map(|w| w.to_vec())
+}
+
+/// A constraint where A·w ∗ B·w != C·w, along with the evaluated linear
+/// combinations and the value of every wire the constraint references
+#[derive(Clone, Debug)]
+pub struct UnsatisfiedConstraint<F: PrimeField> {
+    pub index: usize,
+    pub a: F,
+    pub b: F,
+    pub c: F,
+    pub wires: Vec<(usize, F)>,
+}
+
+impl<F: PrimeField> UnsatisfiedConstraint<F> {
+    /// Display the constraint with each wire's signal name from the `.sym` file
+    pub fn display_with<'a>(&'a self, symbols: &'a SymbolTable) -> impl fmt::Display + 'a {
+        LabelledConstraint {
+            constraint: self,
+            symbols: Some(symbols),
+        }
+    }
+}
+
+struct LabelledConstraint<'a, F: PrimeField> {
+    constraint: &'a UnsatisfiedConstraint<F>,
+    symbols: Option<&'a SymbolTable>,
+}
+
+impl<F: PrimeField> fmt::Display for LabelledConstraint<'_, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let c = self.constraint;
+        writeln!(
+            f,
+            "constraint {}: A·w = {}, B·w = {}, C·w = {}, but A·w ∗ B·w = {}",
+            c.index,
+            c.a,
+            c.b,
+            c.c,
+            c.a * c.b
+        )?;
+        for (wire, value) in &c.wires {
+            match self.symbols.and_then(|s| s.name(*wire)) {
+                Some(name) => writeln!(f, "  wire {} ({}) = {}", wire, name, value)?,
+                None => writeln!(f, "  wire {} = {}", wire, value)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<F: PrimeField> fmt::Display for UnsatisfiedConstraint<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        LabelledConstraint {
+            constraint: self,
+            symbols: None,
         }
+        .fmt(f)
     }
 }
 
+/// Evaluate every constraint against the witness, returning those that fail
+fn check_witness<F: PrimeField>(
+    r1cs: &R1CS<F>,
+    witness: Option<&[F]>,
+) -> Result<Vec<UnsatisfiedConstraint<F>>, SynthesisError> {
+    let witness = witness.ok_or(SynthesisError::AssignmentMissing)?;
+    let value = |i: usize| {
+        witness
+            .get(i)
+            .copied()
+            .ok_or(SynthesisError::AssignmentMissing)
+    };
+    let eval = |lc: &[(usize, F)]| {
+        lc.iter()
+            .try_fold(F::zero(), |acc, (i, coeff)| Ok(acc + *coeff * value(*i)?))
+    };
+
+    let mut unsatisfied = Vec::new();
+    for (index, (a, b, c)) in r1cs.constraints.iter().enumerate() {
+        let (a_val, b_val, c_val) = (eval(a)?, eval(b)?, eval(c)?);
+        if a_val * b_val == c_val {
+            continue;
+        }
+        let mut wires: Vec<usize> = a.iter().chain(b).chain(c).map(|(i, _)| *i).collect();
+        wires.sort_unstable();
+        wires.dedup();
+        unsatisfied.push(UnsatisfiedConstraint {
+            index,
+            a: a_val,
+            b: b_val,
+            c: c_val,
+            wires: wires
+                .into_iter()
+                .map(|i| Ok((i, value(i)?)))
+                .collect::<Result<_, SynthesisError>>()?,
+        });
+    }
+    Ok(unsatisfied)
+}
+
 impl<F: PrimeField> ConstraintSynthesizer<F> for CircomCircuit<F> {
     fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
-        let witness = &self.witness;
-        let wire_mapping = &self.r1cs.wire_mapping;
-
-        // Start from 1 because Arkworks implicitly allocates One for the first input
-        for i in 1..self.r1cs.num_inputs {
-            cs.new_input_variable(|| {
-                Ok(match witness {
-                    None => F::from(1u32),
-                    Some(w) => match wire_mapping {
-                        Some(m) => w[m[i]],
-                        None => w[i],
-                    },
-                })
-            })?;
+        synthesize(&self.r1cs, self.witness.as_deref(), cs)
+    }
+}
+
+impl<F: PrimeField> ConstraintSynthesizer<F> for CircomCircuitRef<'_, F> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        synthesize(self.r1cs, self.witness, cs)
+    }
+}
+
+fn synthesize<F: PrimeField>(
+    r1cs: &R1CS<F>,
+    witness: Option<&[F]>,
+    cs: ConstraintSystemRef<F>,
+) -> Result<(), SynthesisError> {
+    // A witness that is too short for the circuit is reported as a missing
+    // assignment, rather than panicking on an out of bounds index
+    let assignment = |i: usize| -> Result<F, SynthesisError> {
+        match witness {
+            None => Ok(F::from(1u32)),
+            Some(w) => w.get(i).copied().ok_or(SynthesisError::AssignmentMissing),
         }
+    };
+
+    // Start from 1 because Arkworks implicitly allocates One for the first input
+    for i in 1..r1cs.num_inputs {
+        cs.new_input_variable(|| assignment(i))?;
+    }
+
+    for i in 0..r1cs.num_aux {
+        cs.new_witness_variable(|| assignment(i + r1cs.num_inputs))?;
+    }
 
-        for i in 0..self.r1cs.num_aux {
-            cs.new_witness_variable(|| {
-                Ok(match witness {
-                    None => F::from(1u32),
-                    Some(w) => match wire_mapping {
-                        Some(m) => w[m[i + self.r1cs.num_inputs]],
-                        None => w[i + self.r1cs.num_inputs],
-                    },
-                })
-            })?;
+    let make_index = |index| {
+        if index < r1cs.num_inputs {
+            Variable::Instance(index)
+        } else {
+            Variable::Witness(index - r1cs.num_inputs)
         }
+    };
+    let make_lc = |lc_data: &[(usize, F)]| {
+        lc_data.iter().fold(
+            LinearCombination::<F>::zero(),
+            |lc: LinearCombination<F>, (index, coeff)| lc + (*coeff, make_index(*index)),
+        )
+    };
 
-        let make_index = |index| {
-            if index < self.r1cs.num_inputs {
-                Variable::Instance(index)
-            } else {
-                Variable::Witness(index - self.r1cs.num_inputs)
-            }
-        };
-        let make_lc = |lc_data: &[(usize, F)]| {
-            lc_data.iter().fold(
-                LinearCombination::<F>::zero(),
-                |lc: LinearCombination<F>, (index, coeff)| lc + (*coeff, make_index(*index)),
-            )
-        };
+    for constraint in &r1cs.constraints {
+        cs.enforce_constraint(
+            make_lc(&constraint.0),
+            make_lc(&constraint.1),
+            make_lc(&constraint.2),
+        )?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::r1cs_reader::R1CSFile;
+    use ark_bn254::Fr;
+    use std::io::Cursor;
+
+    /// `example/multiply.r1cs` with the wire to label map of an optimized
+    /// circuit, where labels skip the signals the optimizer removed
+    pub(crate) fn relabelled_multiply() -> Vec<u8> {
+        let mut data = include_bytes!("../example/multiply.r1cs").to_vec();
+        // The header's label count, then the map in the last section
+        data[0xd0..0xd8].copy_from_slice(&7u64.to_le_bytes());
+        for (wire, label) in [0u64, 2, 5, 6].into_iter().enumerate() {
+            let offset = 0xe8 + 8 * wire;
+            data[offset..offset + 8].copy_from_slice(&label.to_le_bytes());
+        }
+        data
+    }
 
-        for constraint in &self.r1cs.constraints {
-            cs.enforce_constraint(
-                make_lc(&constraint.0),
-                make_lc(&constraint.1),
-                make_lc(&constraint.2),
-            )?;
+    fn multiply(witness: &[u64]) -> CircomCircuit<Fr> {
+        let data = include_bytes!("../example/multiply.r1cs");
+        CircomCircuit {
+            r1cs: R1CSFile::new(Cursor::new(&data[..]))
+                .unwrap()
+                .try_into()
+                .unwrap(),
+            witness: Some(witness.iter().map(|v| Fr::from(*v)).collect()),
         }
+    }
 
-        Ok(())
+    #[test]
+    fn check_witness() {
+        assert!(multiply(&[1, 110, 11, 10])
+            .check_witness()
+            .unwrap()
+            .is_empty());
+
+        let unsatisfied = multiply(&[1, 111, 11, 10]).check_witness().unwrap();
+        assert_eq!(unsatisfied.len(), 1);
+        assert_eq!(unsatisfied[0].index, 0);
+        assert_eq!(
+            unsatisfied[0].wires,
+            vec![(1, Fr::from(111)), (2, Fr::from(11)), (3, Fr::from(10))]
+        );
+
+        assert!(multiply(&[1, 110]).check_witness().is_err());
+
+        // Wires are looked up by index, not by their label
+        let data = relabelled_multiply();
+        let relabelled = |witness: [u64; 4]| CircomCircuit {
+            r1cs: R1CSFile::new(Cursor::new(&data))
+                .unwrap()
+                .try_into()
+                .unwrap(),
+            witness: Some(witness.map(Fr::from).to_vec()),
+        };
+        assert!(relabelled([1, 110, 11, 10])
+            .check_witness()
+            .unwrap()
+            .is_empty());
+        let unsatisfied = relabelled([1, 111, 11, 10]).check_witness().unwrap();
+        assert_eq!(
+            unsatisfied[0].wires,
+            vec![(1, Fr::from(111)), (2, Fr::from(11)), (3, Fr::from(10))]
+        );
     }
 }
@@ -0,0 +1,106 @@
+use std::fmt;
+use std::str::FromStr;
+
+use ark_bn254::Bn254;
+use ark_ec::pairing::Pairing;
+use ark_ec::short_weierstrass::{Affine, SWCurveConfig};
+
+use crate::error::Error;
+
+/// A pairing curve circom can compile for, where both groups are short
+/// Weierstrass curves so points can be built from their coordinates.
+pub trait CircomCurve:
+    Pairing<G1Affine = Affine<Self::G1Config>, G2Affine = Affine<Self::G2Config>>
+{
+    type G1Config: SWCurveConfig;
+    type G2Config: SWCurveConfig;
+
+    /// Name used in the `curve` field of JSON proofs and verifying keys
+    const NAME: &'static str;
+}
+
+impl CircomCurve for Bn254 {
+    type G1Config = ark_bn254::g1::Config;
+    type G2Config = ark_bn254::g2::Config;
+    const NAME: &'static str = "bn128";
+}
+
+#[cfg(feature = "bls12-381")]
+impl CircomCurve for ark_bls12_381::Bls12_381 {
+    type G1Config = ark_bls12_381::g1::Config;
+    type G2Config = ark_bls12_381::g2::Config;
+    const NAME: &'static str = "bls12381";
+}
+
+#[cfg(feature = "bls12-377")]
+impl CircomCurve for ark_bls12_377::Bls12_377 {
+    type G1Config = ark_bls12_377::g1::Config;
+    type G2Config = ark_bls12_377::g2::Config;
+    const NAME: &'static str = "bls12377";
+}
+
+/// Runtime choice of [`CircomCurve`], e.g. from a command-line flag or the
+/// `curve` field of a JSON file
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Curve {
+    Bn254,
+    #[cfg(feature = "bls12-381")]
+    Bls12_381,
+    #[cfg(feature = "bls12-377")]
+    Bls12_377,
+}
+
+impl Curve {
+    pub fn name(self) -> &'static str {
+        match self {
+            Curve::Bn254 => Bn254::NAME,
+            #[cfg(feature = "bls12-381")]
+            Curve::Bls12_381 => ark_bls12_381::Bls12_381::NAME,
+            #[cfg(feature = "bls12-377")]
+            Curve::Bls12_377 => ark_bls12_377::Bls12_377::NAME,
+        }
+    }
+
+    /// Every curve this build supports
+    pub fn all() -> Vec<Curve> {
+        vec![
+            Curve::Bn254,
+            #[cfg(feature = "bls12-381")]
+            Curve::Bls12_381,
+            #[cfg(feature = "bls12-377")]
+            Curve::Bls12_377,
+        ]
+    }
+
+    /// Read the curve from the `curve` field of a JSON proof or verifying key
+    pub fn from_json(json_str: &str) -> Result<Self, Error> {
+        #[derive(serde::Deserialize)]
+        struct Tagged {
+            curve: String,
+        }
+        let tagged: Tagged = serde_json::from_str(json_str)?;
+        tagged.curve.parse()
+    }
+}
+
+impl fmt::Display for Curve {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Accepts the circom / snarkjs names, as well as the arkworks crate names
+impl FromStr for Curve {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        match s.to_ascii_lowercase().replace(['-', '_'], "").as_str() {
+            "bn128" | "bn254" | "altbn128" => Ok(Curve::Bn254),
+            #[cfg(feature = "bls12-381")]
+            "bls12381" => Ok(Curve::Bls12_381),
+            #[cfg(feature = "bls12-377")]
+            "bls12377" => Ok(Curve::Bls12_377),
+            _ => Err(Error::UnsupportedCurve(s.to_string())),
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use ark_relations::r1cs::SynthesisError;
+use ark_serialize::SerializationError;
+use thiserror::Error;
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Every fallible function in this crate returns this error, so callers can
+/// tell bad inputs apart from corrupt artifacts or failed proofs.
+#[derive(Debug, Error)]
+pub enum Error {
+    /// A value in the inputs JSON is malformed, unknown or the wrong length
+    #[error("invalid input '{key}': {message}")]
+    Input { key: String, message: String },
+
+    /// Input signals of the circuit are absent from the inputs JSON, each with
+    /// the number of values it expects
+    #[error("missing input signals: {}", describe_missing(.0))]
+    MissingInputs(Vec<(String, usize)>),
+
+    /// A typed inputs struct doesn't have the witness graph's input signals
+    #[error("inputs don't match the circuit: {}", .0.join("; "))]
+    InputShape(Vec<String>),
+
+    /// A field element isn't a canonical decimal or `0x` hex number below the modulus
+    #[error("invalid field element '{value}': {message}")]
+    FieldElement { value: String, message: String },
+
+    /// The `.r1cs` file is malformed, `offset` is where reading stopped
+    #[error("invalid r1cs at byte offset {offset}: {message}")]
+    R1cs { offset: u64, message: String },
+
+    /// The `.zkey` file is malformed, `offset` is where reading stopped
+    #[error("invalid zkey at byte offset {offset}: {message}")]
+    Zkey { offset: u64, message: String },
+
+    /// The `.wtns` file is malformed, `offset` is where reading stopped
+    #[error("invalid wtns at byte offset {offset}: {message}")]
+    Wtns { offset: u64, message: String },
+
+    /// The `.sym` file is malformed, `line` counts from 1
+    #[error("invalid sym at line {line}: {message}")]
+    Sym { line: usize, message: String },
+
+    /// Two files passed together were made for different circuits
+    #[error("mismatched artifacts: {0}")]
+    Mismatch(String),
+
+    /// The circuit applies custom gates, which have no Groth16 constraints
+    #[error("circuit uses custom gates, which can't be proven with Groth16: {}", .0.join(", "))]
+    CustomGates(Vec<String>),
+
+    /// The curve name isn't known, or support for it wasn't compiled in
+    #[error("unsupported curve '{0}'")]
+    UnsupportedCurve(String),
+
+    /// The QAP reduction name isn't known
+    #[error("unsupported QAP reduction '{0}'")]
+    UnsupportedReduction(String),
+
+    /// The circom-witnesscalc graph could not be loaded
+    #[error("invalid witness graph: {0}")]
+    Graph(String),
+
+    /// A binary proving or verifying key could not be deserialized
+    #[error("invalid {kind}: {source}")]
+    Key {
+        kind: &'static str,
+        source: SerializationError,
+    },
+
+    /// A binary proof could not be deserialized
+    #[error("invalid proof: {0}")]
+    Proof(SerializationError),
+
+    /// A JSON proof or key is malformed, `path` locates the bad value
+    #[error("invalid JSON at {path}: {message}")]
+    Json { path: String, message: String },
+
+    /// The witness computed from the inputs does not satisfy the R1CS
+    #[error("witness does not satisfy constraint {constraint}")]
+    Unsatisfied { constraint: usize },
+
+    /// The verifier rejected its arguments, e.g. the wrong number of inputs
+    #[error("verification failed: {0}")]
+    Verification(String),
+
+    #[error(transparent)]
+    Synthesis(#[from] SynthesisError),
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+impl Error {
+    pub(crate) fn input(key: impl Into<String>, message: impl ToString) -> Self {
+        Error::Input {
+            key: key.into(),
+            message: message.to_string(),
+        }
+    }
+
+    pub(crate) fn json(path: impl Into<String>, message: impl ToString) -> Self {
+        Error::Json {
+            path: path.into(),
+            message: message.to_string(),
+        }
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::json("$", e)
+    }
+}
+
+fn describe_missing(missing: &[(String, usize)]) -> String {
+    missing
+        .iter()
+        .map(|(name, len)| match len {
+            1 => format!("{} (1 value)", name),
+            len => format!("{} ({} values)", name, len),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
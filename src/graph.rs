@@ -0,0 +1,116 @@
+//! A circom-witnesscalc witness graph, loaded and checked for one prime field
+
+use ark_ff::{BigInteger, PrimeField};
+
+use circom_witnesscalc::{
+    field::{Field, FieldOps, U254},
+    graph::{Nodes, NodesInterface, VecNodes},
+    storage::proto_deserializer::deserialize_witnesscalc_graph_from_bytes,
+    InputSignalsInfo,
+};
+
+use crate::error::{Error, Result};
+
+pub(crate) struct Graph {
+    nodes: Box<dyn NodesInterface>,
+    signals: Vec<usize>,
+    pub(crate) input_mapping: InputSignalsInfo,
+}
+
+impl Graph {
+    /// Load a graph, an error unless it was compiled for the prime field `F`
+    pub(crate) fn new<F: PrimeField>(graph_data: &[u8]) -> Result<Self> {
+        check_graph_field::<F>()?;
+        let (nodes, signals, input_mapping) = deserialize_witnesscalc_graph_from_bytes(graph_data)
+            .map_err(|e| Error::Graph(e.to_string()))?;
+        let graph = Graph {
+            nodes,
+            signals,
+            input_mapping,
+        };
+        check_graph_prime::<F, _>(&graph.nodes()?.ff)?;
+        Ok(graph)
+    }
+
+    fn nodes(&self) -> Result<&Nodes<U254, VecNodes>> {
+        self.nodes
+            .as_any()
+            .downcast_ref::<Nodes<U254, VecNodes>>()
+            .ok_or_else(|| Error::Graph("expected U254 nodes".to_string()))
+    }
+
+    /// Compute the witness with `eval`, which is given the nodes, the input
+    /// mapping and the output signals
+    pub(crate) fn evaluate<F: PrimeField>(
+        &self,
+        eval: impl FnOnce(&Nodes<U254, VecNodes>, &InputSignalsInfo, &[usize]) -> Result<Vec<U254>>,
+    ) -> Result<Vec<F>> {
+        let result = eval(self.nodes()?, &self.input_mapping, &self.signals)?;
+        Ok(result
+            .iter()
+            .map(|a| F::from_le_bytes_mod_order(a.as_le_slice()))
+            .collect())
+    }
+}
+
+/// Witness graphs hold their values as `U254`, too small for a larger prime
+/// such as BLS12-381's. witnesscalc panics reading such a graph, so check first.
+fn check_graph_field<F: PrimeField>() -> Result<()> {
+    if F::MODULUS_BIT_SIZE > 254 {
+        return Err(Error::Graph(format!(
+            "witness graphs only hold 254 bit values, the curve's prime has {} bits, \
+             prove a witness computed elsewhere instead",
+            F::MODULUS_BIT_SIZE
+        )));
+    }
+    Ok(())
+}
+
+/// Whether a witness graph was compiled for the prime field `F`
+fn check_graph_prime<F: PrimeField, T: FieldOps>(ff: &Field<T>) -> Result<()> {
+    let trim = |b: &[u8]| b.len() - b.iter().rev().take_while(|&&x| x == 0).count();
+    let prime = ff.prime.as_le_slice();
+    let modulus = F::MODULUS.to_bytes_le();
+    if prime[..trim(prime)] != modulus[..trim(&modulus)] {
+        return Err(Error::Graph(
+            "graph prime does not match the curve's scalar field".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bn254::Fr;
+
+    static GRAPH_DATA: &[u8] = include_bytes!("../example/multiply.graph");
+
+    #[cfg(feature = "bls12-381")]
+    #[test]
+    fn graph_prime_must_match_the_curve() {
+        // The example graph is compiled for BN254, and no graph can be for BLS12-381
+        assert!(Graph::new::<Fr>(GRAPH_DATA).is_ok());
+        assert!(matches!(
+            Graph::new::<ark_bls12_381::Fr>(GRAPH_DATA),
+            Err(Error::Graph(_))
+        ));
+    }
+
+    #[test]
+    fn graphs_are_evaluated_into_the_field() {
+        let graph = Graph::new::<Fr>(GRAPH_DATA).unwrap();
+        let witness: Vec<Fr> = graph
+            .evaluate(|nodes, input_mapping, signals| {
+                crate::utils::calc_witness_typed(
+                    nodes,
+                    r#"{"a": "11", "b": "10"}"#,
+                    input_mapping,
+                    signals,
+                    crate::MissingInputs::Error,
+                )
+            })
+            .unwrap();
+        assert_eq!(witness, [1, 110, 11, 10].map(Fr::from));
+    }
+}
@@ -0,0 +1,455 @@
+//! Building circuit inputs from Rust values, rather than a JSON string
+//!
+//! ```rust,ignore
+//! let inputs = Inputs::<Fr>::new()
+//!     .set("a", 11u64)
+//!     .set("b", Fr::from(10))
+//!     .set("tx.amount", vec![1u64, 2, 3])
+//!     .set("hash", &digest[..]);
+//! let (proof, public_inputs) = prover.prove_inputs(&inputs)?;
+//! ```
+//!
+//! Or from a struct with the same shape as the circuit's inputs, checked
+//! against the witness graph when the prover is loaded:
+//!
+//! ```rust,ignore
+//! #[derive(CircomInputs)]
+//! struct Transfer {
+//!     #[circom(rename = "nullifierHash")]
+//!     nullifier_hash: Fr,
+//!     path: [[Fr; 2]; 20],
+//!     tx: Tx,
+//! }
+//!
+//! let prover = Prover::new(&PKEY_DATA, &GRAPH_DATA, &R1CS_DATA)?.with_inputs::<Transfer>()?;
+//! let (proof, public_inputs) = prover.prove(&transfer)?;
+//! ```
+
+use std::collections::HashMap;
+
+use ark_ff::{BigInteger, Fp, FpConfig, PrimeField};
+use circom_witnesscalc::{field::U254, InputSignalsInfo};
+
+use crate::error::{Error, Result};
+
+/// A value for one input signal, which may be an array of values. Byte
+/// slices and arrays are one big-endian integer, like a hash, reduced mod p.
+/// `u8` isn't a value on its own, so bytes aren't mistaken for an array.
+pub trait InputValue<F: PrimeField> {
+    /// Append the flattened values of this input
+    fn push_values(self, values: &mut Vec<F>);
+}
+
+impl<P: FpConfig<N>, const N: usize> InputValue<Fp<P, N>> for Fp<P, N> {
+    fn push_values(self, values: &mut Vec<Fp<P, N>>) {
+        values.push(self);
+    }
+}
+
+macro_rules! impl_input_value {
+    ($($t:ty),*) => {
+        $(
+            impl<F: PrimeField> InputValue<F> for $t {
+                fn push_values(self, values: &mut Vec<F>) {
+                    values.push(F::from(self));
+                }
+            }
+        )*
+    };
+}
+
+// Negative integers are reduced mod p, as circom does
+impl_input_value!(bool, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
+impl<F: PrimeField> InputValue<F> for usize {
+    fn push_values(self, values: &mut Vec<F>) {
+        values.push(F::from(self as u64));
+    }
+}
+
+impl<F: PrimeField> InputValue<F> for &[u8] {
+    fn push_values(self, values: &mut Vec<F>) {
+        values.push(F::from_be_bytes_mod_order(self));
+    }
+}
+
+impl<F: PrimeField, const N: usize> InputValue<F> for [u8; N] {
+    fn push_values(self, values: &mut Vec<F>) {
+        values.push(F::from_be_bytes_mod_order(&self));
+    }
+}
+
+impl<F: PrimeField, T: InputValue<F>> InputValue<F> for Vec<T> {
+    fn push_values(self, values: &mut Vec<F>) {
+        for value in self {
+            value.push_values(values);
+        }
+    }
+}
+
+/// A field of a [`CircomInputs`] struct. Field elements, integers and
+/// fixed-size arrays of them are one signal, flattened in order. Structs and
+/// arrays of structs are a signal per field, e.g. `tx.to` or `txs[1].to`.
+pub trait CircomInput<F: PrimeField> {
+    /// The number of values as one signal, `None` for a struct
+    fn value_len() -> Option<usize>;
+
+    /// Append the flattened values, only when [`CircomInput::value_len`] is `Some`
+    fn push_values(&self, values: &mut Vec<F>);
+
+    /// Every signal under `name` with its number of values
+    fn signals(name: &str, signals: &mut Vec<(String, usize)>) {
+        let len = Self::value_len().expect("structs list the signals of their fields");
+        signals.push((name.to_string(), len));
+    }
+
+    fn insert_into(&self, name: &str, inputs: &mut Inputs<F>) {
+        let mut values = Vec::new();
+        self.push_values(&mut values);
+        inputs.signals.insert(name.to_string(), values);
+    }
+}
+
+impl<P: FpConfig<N>, const N: usize> CircomInput<Fp<P, N>> for Fp<P, N> {
+    fn value_len() -> Option<usize> {
+        Some(1)
+    }
+
+    fn push_values(&self, values: &mut Vec<Fp<P, N>>) {
+        values.push(*self);
+    }
+}
+
+macro_rules! impl_circom_input {
+    ($($t:ty),*) => {
+        $(
+            impl<F: PrimeField> CircomInput<F> for $t {
+                fn value_len() -> Option<usize> {
+                    Some(1)
+                }
+
+                fn push_values(&self, values: &mut Vec<F>) {
+                    InputValue::push_values(*self, values);
+                }
+            }
+        )*
+    };
+}
+
+impl_circom_input!(bool, u16, u32, u64, u128, i8, i16, i32, i64, i128, usize);
+
+impl<F: PrimeField, T: CircomInput<F>, const N: usize> CircomInput<F> for [T; N] {
+    fn value_len() -> Option<usize> {
+        T::value_len().map(|len| len * N)
+    }
+
+    fn push_values(&self, values: &mut Vec<F>) {
+        for value in self {
+            value.push_values(values);
+        }
+    }
+
+    fn signals(name: &str, signals: &mut Vec<(String, usize)>) {
+        match Self::value_len() {
+            Some(len) => signals.push((name.to_string(), len)),
+            None => (0..N).for_each(|i| T::signals(&format!("{}[{}]", name, i), signals)),
+        }
+    }
+
+    fn insert_into(&self, name: &str, inputs: &mut Inputs<F>) {
+        match Self::value_len() {
+            Some(_) => {
+                let mut values = Vec::new();
+                self.push_values(&mut values);
+                inputs.signals.insert(name.to_string(), values);
+            }
+            None => {
+                for (i, value) in self.iter().enumerate() {
+                    value.insert_into(&format!("{}[{}]", name, i), inputs);
+                }
+            }
+        }
+    }
+}
+
+/// A struct of every input signal of a circuit, see `#[derive(CircomInputs)]`
+pub trait CircomInputs<F: PrimeField>: CircomInput<F> {
+    /// Every input signal by flattened name, with its number of values
+    fn input_signals() -> Vec<(String, usize)> {
+        let mut signals = Vec::new();
+        Self::signals("", &mut signals);
+        signals
+    }
+
+    fn to_inputs(&self) -> Inputs<F> {
+        let mut inputs = Inputs::new();
+        self.insert_into("", &mut inputs);
+        inputs
+    }
+
+    /// Check the struct has the inputs of the witness graph, with the same
+    /// lengths, listing every signal that differs
+    fn check_signals(input_mapping: &InputSignalsInfo) -> Result<()> {
+        let signals = Self::input_signals();
+        let mut mismatches = Vec::new();
+        for (name, len) in &signals {
+            match input_mapping.get(name) {
+                None => mismatches.push(format!("'{}' isn't an input of the circuit", name)),
+                Some((_, expected)) if expected != len => mismatches.push(format!(
+                    "'{}' has {} values, the circuit expects {}",
+                    name, len, expected
+                )),
+                Some(_) => {}
+            }
+        }
+        let mut missing: Vec<_> = input_mapping
+            .iter()
+            .filter(|(name, _)| !signals.iter().any(|(s, _)| s == *name))
+            .collect();
+        missing.sort_by_key(|(_, (offset, _))| *offset);
+        for (name, (_, len)) in missing {
+            let plural = if *len == 1 { "" } else { "s" };
+            mismatches.push(format!("no field for '{}' ({} value{})", name, len, plural));
+        }
+        if mismatches.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::InputShape(mismatches))
+        }
+    }
+}
+
+#[doc(hidden)]
+pub mod __private {
+    pub use ark_ff::PrimeField;
+
+    /// The signal name of a struct field, `name` is empty at the top level
+    pub fn field_name(name: &str, field: &str) -> String {
+        match name {
+            "" => field.to_string(),
+            name => format!("{}.{}", name, field),
+        }
+    }
+}
+
+/// The values of each input signal by name, as they would be given in the
+/// inputs JSON. Nested signals use their flattened names, e.g. `tx.from` or
+/// `tx[0].from`, and arrays are flattened in order.
+///
+/// Signals are checked against the witness graph when the witness is computed:
+/// unknown names, wrong lengths and missing signals are errors.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Inputs<F: PrimeField> {
+    signals: HashMap<String, Vec<F>>,
+}
+
+impl<F: PrimeField> Inputs<F> {
+    pub fn new() -> Self {
+        Inputs {
+            signals: HashMap::new(),
+        }
+    }
+
+    /// Set a signal, replacing any previous value
+    pub fn set(mut self, name: impl Into<String>, value: impl InputValue<F>) -> Self {
+        self.insert(name, value);
+        self
+    }
+
+    /// Same as [`Inputs::set`], for building inputs in a loop
+    pub fn insert(&mut self, name: impl Into<String>, value: impl InputValue<F>) -> &mut Self {
+        let mut values = Vec::new();
+        value.push_values(&mut values);
+        self.signals.insert(name.into(), values);
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&[F]> {
+        self.signals.get(name).map(Vec::as_slice)
+    }
+
+    pub fn len(&self) -> usize {
+        self.signals.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.signals.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &[F])> {
+        self.signals
+            .iter()
+            .map(|(name, values)| (name.as_str(), values.as_slice()))
+    }
+
+    /// The values in the witness graph's representation, for [`crate::utils::create_inputs`]
+    pub(crate) fn to_graph_values(&self) -> Result<HashMap<String, Vec<U254>>> {
+        self.signals
+            .iter()
+            .map(|(name, values)| {
+                let values = values
+                    .iter()
+                    .map(|v| {
+                        U254::try_from_le_slice(&v.into_bigint().to_bytes_le()).ok_or_else(|| {
+                            Error::input(name, "value doesn't fit the witness graph's field")
+                        })
+                    })
+                    .collect::<Result<_>>()?;
+                Ok((name.clone(), values))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{create_inputs, MissingInputs};
+    use ark_bn254::Fr;
+    use circom_witnesscalc::InputSignalsInfo;
+
+    #[test]
+    fn build_inputs() {
+        let digest = [0xffu8; 32];
+        let inputs = Inputs::<Fr>::new()
+            .set("a", 11u64)
+            .set("b", Fr::from(10))
+            .set("flag", true)
+            .set("neg", -1i64)
+            .set("tx.amount", vec![vec![1u32, 2], vec![3]])
+            .set("tx.memo", vec![Fr::from(4), Fr::from(5)])
+            .set("hash", &digest[..]);
+
+        assert_eq!(inputs.len(), 7);
+        assert_eq!(inputs.get("a"), Some(&[Fr::from(11)][..]));
+        assert_eq!(inputs.get("flag"), Some(&[Fr::from(1)][..]));
+        assert_eq!(inputs.get("neg"), Some(&[-Fr::from(1)][..]));
+        assert_eq!(
+            inputs.get("tx.amount"),
+            Some(&[Fr::from(1), Fr::from(2), Fr::from(3)][..])
+        );
+        assert_eq!(inputs.get("tx.memo").unwrap().len(), 2);
+        assert_eq!(
+            inputs.get("hash"),
+            Some(&[Fr::from_be_bytes_mod_order(&digest)][..])
+        );
+        assert_eq!(inputs.get("c"), None);
+    }
+
+    #[test]
+    fn inputs_are_checked_against_the_graph() {
+        let info: InputSignalsInfo = [("a".to_string(), (1, 1)), ("b".to_string(), (2, 2))]
+            .into_iter()
+            .collect();
+
+        let inputs = Inputs::<Fr>::new()
+            .set("a", -Fr::from(1))
+            .set("b", vec![2u64, 3]);
+        let values = create_inputs(&inputs.to_graph_values().unwrap(), &info).unwrap();
+        let p_minus_1 = U254::from_le_slice(&(-Fr::from(1)).into_bigint().to_bytes_le());
+        assert_eq!(values[1], p_minus_1);
+        assert_eq!(values[2..4], [U254::from(2u64), U254::from(3u64)]);
+
+        let wrong_length = Inputs::<Fr>::new().set("a", 1u64).set("b", 2u64);
+        assert!(matches!(
+            create_inputs(&wrong_length.to_graph_values().unwrap(), &info),
+            Err(Error::Input { .. })
+        ));
+        let missing = Inputs::<Fr>::new().set("a", 1u64);
+        let graph_values = missing.to_graph_values().unwrap();
+        assert!(matches!(
+            create_inputs(&graph_values, &info),
+            Err(Error::MissingInputs(_))
+        ));
+        assert!(
+            crate::utils::create_inputs_with(&graph_values, &info, MissingInputs::Zero).is_ok()
+        );
+    }
+
+    #[cfg(feature = "derive")]
+    #[test]
+    fn derived_inputs_match_the_graph() {
+        use crate::CircomInputs;
+
+        #[derive(CircomInputs)]
+        struct Tx {
+            to: Fr,
+            amount: [u64; 2],
+        }
+
+        #[derive(CircomInputs)]
+        struct Transfer {
+            #[circom(rename = "nullifierHash")]
+            nullifier_hash: Fr,
+            path: [[u32; 2]; 3],
+            tx: Tx,
+            history: [Tx; 2],
+        }
+
+        let tx = |n: u64| Tx {
+            to: Fr::from(n),
+            amount: [n + 1, n + 2],
+        };
+        let transfer = Transfer {
+            nullifier_hash: Fr::from(7),
+            path: [[1, 2], [3, 4], [5, 6]],
+            tx: tx(10),
+            history: [tx(20), tx(30)],
+        };
+
+        let signals = Transfer::input_signals();
+        let names: Vec<_> = signals
+            .iter()
+            .map(|(name, len)| (name.as_str(), *len))
+            .collect();
+        assert_eq!(
+            names,
+            [
+                ("nullifierHash", 1),
+                ("path", 6),
+                ("tx.to", 1),
+                ("tx.amount", 2),
+                ("history[0].to", 1),
+                ("history[0].amount", 2),
+                ("history[1].to", 1),
+                ("history[1].amount", 2),
+            ]
+        );
+
+        let inputs = transfer.to_inputs();
+        assert_eq!(inputs.len(), 8);
+        assert_eq!(inputs.get("path").unwrap()[5], Fr::from(6));
+        assert_eq!(
+            inputs.get("history[1].amount"),
+            Some(&[Fr::from(31), Fr::from(32)][..])
+        );
+
+        let mut offset = 1;
+        let mut info: InputSignalsInfo = signals
+            .iter()
+            .map(|(name, len)| {
+                offset += len;
+                (name.clone(), (offset - len, *len))
+            })
+            .collect();
+        Transfer::check_signals(&info).unwrap();
+        let values = create_inputs(&inputs.to_graph_values().unwrap(), &info).unwrap();
+        assert_eq!(values[2..8], [1u64, 2, 3, 4, 5, 6].map(U254::from));
+
+        info.get_mut("path").unwrap().1 = 4;
+        info.remove("tx.to");
+        info.insert("fee".to_string(), (offset, 1));
+        let err = Transfer::check_signals(&info).unwrap_err();
+        let Error::InputShape(mismatches) = &err else {
+            panic!("{}", err);
+        };
+        assert_eq!(
+            mismatches,
+            &[
+                "'path' has 6 values, the circuit expects 4",
+                "'tx.to' isn't an input of the circuit",
+                "no field for 'fee' (1 value)",
+            ]
+        );
+    }
+}
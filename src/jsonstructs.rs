@@ -1,10 +1,14 @@
-use anyhow::{anyhow, Result};
-use ark_bn254::{Bn254, Fq, Fq2, Fr};
-use ark_ec::pairing::Pairing;
-use ark_ff::BigInt;
+use ark_ec::short_weierstrass::{Affine, SWCurveConfig};
+use ark_ff::{Field, PrimeField, Zero};
 use ark_groth16::{Proof, VerifyingKey};
+use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
-use std::str::FromStr;
+
+use crate::curve::CircomCurve;
+use crate::error::{Error, Result};
+use crate::snarkjs::{is_snarkjs_verifying_key, SnarkjsVerifyingKeyJson};
+use crate::sym::PublicSignals;
+use crate::utils::{n_public, parse_field_element};
 
 // JSON representation structs
 #[derive(Serialize, Deserialize)]
@@ -31,97 +35,140 @@ pub struct ProofJson {
     b: [[String; 2]; 2],
     c: [String; 2],
     inputs: Vec<String>,
+    /// The inputs by signal name, when the proof was made with a symbol table
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    public: Option<IndexMap<String, String>>,
 }
 
 // Helper functions to reduce duplication
-fn g1_point_to_strings(point: &<Bn254 as Pairing>::G1Affine) -> [String; 2] {
+fn g1_point_to_strings<P: SWCurveConfig>(point: &Affine<P>) -> [String; 2] {
     [point.x.to_string(), point.y.to_string()]
 }
 
-fn g2_point_to_strings(point: &<Bn254 as Pairing>::G2Affine) -> [[String; 2]; 2] {
-    [
-        [point.x.c1.to_string(), point.x.c0.to_string()],
-        [point.y.c1.to_string(), point.y.c0.to_string()],
-    ]
+/// G2 coordinates are written `[c1, c0]`, the order the EVM pairing precompile expects
+fn g2_point_to_strings<P: SWCurveConfig>(point: &Affine<P>) -> [[String; 2]; 2] {
+    let limbs = |f: &P::BaseField| -> [String; 2] {
+        let c: Vec<String> = f
+            .to_base_prime_field_elements()
+            .map(|c| c.to_string())
+            .collect();
+        [c[1].clone(), c[0].clone()]
+    };
+    [limbs(&point.x), limbs(&point.y)]
+}
+
+pub(crate) fn parse_prime_field<F: PrimeField>(s: &str, path: String, name: &str) -> Result<F> {
+    parse_field_element(s).map_err(|e| match e {
+        Error::FieldElement { value, message } => {
+            Error::json(path, format!("invalid {} '{}': {}", name, value, message))
+        }
+        e => e,
+    })
+}
+
+/// `(0, 0)` is how the point at infinity is written
+pub(crate) fn point_from_coords<P: SWCurveConfig>(
+    x: P::BaseField,
+    y: P::BaseField,
+    path: &str,
+) -> Result<Affine<P>> {
+    if x.is_zero() && y.is_zero() {
+        return Ok(Affine::identity());
+    }
+    let point = Affine::new_unchecked(x, y);
+    if !point.is_on_curve() || !point.is_in_correct_subgroup_assuming_on_curve() {
+        return Err(Error::json(path, "point is not in the curve subgroup"));
+    }
+    Ok(point)
 }
 
-fn parse_g1_point_from_strings(x_str: &str, y_str: &str) -> Result<<Bn254 as Pairing>::G1Affine> {
-    let x_bi = BigInt::<4>::from_str(x_str)
-        .map_err(|_| anyhow!("Failed to parse x coordinate: {}", x_str))?;
-    let y_bi = BigInt::<4>::from_str(y_str)
-        .map_err(|_| anyhow!("Failed to parse y coordinate: {}", y_str))?;
-    let x = Fq::new(x_bi);
-    let y = Fq::new(y_bi);
-    Ok(<Bn254 as Pairing>::G1Affine::new(x, y))
+fn parse_g1_point_from_strings<P: SWCurveConfig>(
+    coords: &[String; 2],
+    path: &str,
+) -> Result<Affine<P>> {
+    let parse = |i: usize, name: &str| -> Result<P::BaseField> {
+        let c = parse_prime_field(&coords[i], format!("{}[{}]", path, i), name)?;
+        P::BaseField::from_base_prime_field_elems([c])
+            .ok_or_else(|| Error::json(path, "G1 coordinates must be prime field elements"))
+    };
+    let x = parse(0, "x coordinate")?;
+    let y = parse(1, "y coordinate")?;
+    point_from_coords(x, y, path)
 }
 
-fn parse_g2_point_from_coords(coords: &[[String; 2]; 2]) -> Result<<Bn254 as Pairing>::G2Affine> {
-    let x_c0_bi = BigInt::<4>::from_str(&coords[0][0])
-        .map_err(|_| anyhow!("Failed to parse x.c0: {}", &coords[0][0]))?;
-    let x_c1_bi = BigInt::<4>::from_str(&coords[0][1])
-        .map_err(|_| anyhow!("Failed to parse x.c1: {}", &coords[0][1]))?;
-    let y_c0_bi = BigInt::<4>::from_str(&coords[1][0])
-        .map_err(|_| anyhow!("Failed to parse y.c0: {}", &coords[1][0]))?;
-    let y_c1_bi = BigInt::<4>::from_str(&coords[1][1])
-        .map_err(|_| anyhow!("Failed to parse y.c1: {}", &coords[1][1]))?;
-
-    let x = Fq2::new(Fq::new(x_c1_bi), Fq::new(x_c0_bi));
-    let y = Fq2::new(Fq::new(y_c1_bi), Fq::new(y_c0_bi));
-    Ok(<Bn254 as Pairing>::G2Affine::new(x, y))
+fn parse_g2_point_from_coords<P: SWCurveConfig>(
+    coords: &[[String; 2]; 2],
+    path: &str,
+) -> Result<Affine<P>> {
+    let parse = |i: usize, name: &str| -> Result<P::BaseField> {
+        let c1 = parse_prime_field(&coords[i][0], format!("{}[{}][0]", path, i), name)?;
+        let c0 = parse_prime_field(&coords[i][1], format!("{}[{}][1]", path, i), name)?;
+        P::BaseField::from_base_prime_field_elems([c0, c1])
+            .ok_or_else(|| Error::json(path, "G2 coordinates must be quadratic extension elements"))
+    };
+    let x = parse(0, "x coordinate")?;
+    let y = parse(1, "y coordinate")?;
+    point_from_coords(x, y, path)
 }
 
-fn parse_field_element(s: &str) -> Result<Fr> {
-    let bi =
-        BigInt::<4>::from_str(s).map_err(|_| anyhow!("Failed to parse field element: {}", s))?;
-    Ok(Fr::new(bi))
+pub(crate) fn check_tag(path: &str, expected: &str, got: &str) -> Result<()> {
+    if got != expected {
+        return Err(Error::json(
+            path,
+            format!("expected '{}', got '{}'", expected, got),
+        ));
+    }
+    Ok(())
 }
 
 // Conversion functions
-impl From<&VerifyingKey<Bn254>> for VerifyingKeyJson {
-    fn from(vk: &VerifyingKey<Bn254>) -> Self {
-        VerifyingKeyJson {
+impl<E: CircomCurve> TryFrom<&VerifyingKey<E>> for VerifyingKeyJson {
+    type Error = Error;
+
+    fn try_from(vk: &VerifyingKey<E>) -> Result<Self> {
+        let n_public = n_public(vk)?;
+        Ok(VerifyingKeyJson {
             protocol: "groth16".to_string(),
-            curve: "bn128".to_string(),
-            n_public: vk.gamma_abc_g1.len() - 1,
+            curve: E::NAME.to_string(),
+            n_public,
             vk_alpha_1: g1_point_to_strings(&vk.alpha_g1),
             vk_beta_2: g2_point_to_strings(&vk.beta_g2),
             vk_gamma_2: g2_point_to_strings(&vk.gamma_g2),
             vk_delta_2: g2_point_to_strings(&vk.delta_g2),
             ic: vk.gamma_abc_g1.iter().map(g1_point_to_strings).collect(),
-        }
+        })
     }
 }
 
-impl TryFrom<VerifyingKeyJson> for VerifyingKey<Bn254> {
-    type Error = anyhow::Error;
+impl<E: CircomCurve> TryFrom<VerifyingKeyJson> for VerifyingKey<E> {
+    type Error = Error;
 
     fn try_from(json: VerifyingKeyJson) -> Result<Self> {
-        if json.protocol != "groth16" {
-            return Err(anyhow!(
-                "Invalid protocol: expected 'groth16', got '{}'",
-                json.protocol
-            ));
-        }
-        if json.curve != "bn128" {
-            return Err(anyhow!(
-                "Invalid curve: expected 'bn128', got '{}'",
-                json.curve
+        check_tag("$.protocol", "groth16", &json.protocol)?;
+        check_tag("$.curve", E::NAME, &json.curve)?;
+        if json.ic.len() != json.n_public + 1 {
+            return Err(Error::json(
+                "$.nPublic",
+                format!(
+                    "expected {} for {} IC points",
+                    json.ic.len().saturating_sub(1),
+                    json.ic.len()
+                ),
             ));
         }
 
         // Parse alpha_g1 using helper function
-        let alpha_g1 = parse_g1_point_from_strings(&json.vk_alpha_1[0], &json.vk_alpha_1[1])?;
+        let alpha_g1 = parse_g1_point_from_strings(&json.vk_alpha_1, "$.vk_alpha_1")?;
 
         // Parse G2 points using helper function
-        let beta_g2 = parse_g2_point_from_coords(&json.vk_beta_2)?;
-        let gamma_g2 = parse_g2_point_from_coords(&json.vk_gamma_2)?;
-        let delta_g2 = parse_g2_point_from_coords(&json.vk_delta_2)?;
+        let beta_g2 = parse_g2_point_from_coords(&json.vk_beta_2, "$.vk_beta_2")?;
+        let gamma_g2 = parse_g2_point_from_coords(&json.vk_gamma_2, "$.vk_gamma_2")?;
+        let delta_g2 = parse_g2_point_from_coords(&json.vk_delta_2, "$.vk_delta_2")?;
 
         // Parse gamma_abc_g1 using helper function
         let mut gamma_abc_g1 = Vec::new();
         for (i, coords) in json.ic.iter().enumerate() {
-            let point = parse_g1_point_from_strings(&coords[0], &coords[1])
-                .map_err(|e| anyhow!("Failed to parse IC[{}]: {}", i, e))?;
+            let point = parse_g1_point_from_strings(coords, &format!("$.IC[{}]", i))?;
             gamma_abc_g1.push(point);
         }
 
@@ -135,12 +182,12 @@ impl TryFrom<VerifyingKeyJson> for VerifyingKey<Bn254> {
     }
 }
 
-impl From<(&Proof<Bn254>, &Vec<Fr>)> for ProofJson {
-    fn from((proof, public_inputs): (&Proof<Bn254>, &Vec<Fr>)) -> Self {
+impl<E: CircomCurve> From<(&Proof<E>, &Vec<E::ScalarField>)> for ProofJson {
+    fn from((proof, public_inputs): (&Proof<E>, &Vec<E::ScalarField>)) -> Self {
         ProofJson {
             protocol: "groth16".to_string(),
             proof_type: "proof".to_string(),
-            curve: "bn128".to_string(),
+            curve: E::NAME.to_string(),
             a: g1_point_to_strings(&proof.a),
             b: g2_point_to_strings(&proof.b),
             c: g1_point_to_strings(&proof.c),
@@ -148,77 +195,125 @@ impl From<(&Proof<Bn254>, &Vec<Fr>)> for ProofJson {
                 .iter()
                 .map(|input| input.to_string())
                 .collect(),
+            public: None,
         }
     }
 }
 
-impl TryFrom<ProofJson> for (Proof<Bn254>, Vec<Fr>) {
-    type Error = anyhow::Error;
+impl<E: CircomCurve> TryFrom<ProofJson> for (Proof<E>, Vec<E::ScalarField>) {
+    type Error = Error;
 
     fn try_from(json: ProofJson) -> Result<Self> {
-        if json.protocol != "groth16" {
-            return Err(anyhow!(
-                "Invalid protocol: expected 'groth16', got '{}'",
-                json.protocol
-            ));
-        }
-        if json.curve != "bn128" {
-            return Err(anyhow!(
-                "Invalid curve: expected 'bn128', got '{}'",
-                json.curve
-            ));
-        }
-        if json.proof_type != "proof" {
-            return Err(anyhow!(
-                "Invalid type: expected 'proof', got '{}'",
-                json.proof_type
-            ));
-        }
+        check_tag("$.protocol", "groth16", &json.protocol)?;
+        check_tag("$.curve", E::NAME, &json.curve)?;
+        check_tag("$.type", "proof", &json.proof_type)?;
 
         // Parse G1 points using helper function
-        let a = parse_g1_point_from_strings(&json.a[0], &json.a[1])?;
-        let c = parse_g1_point_from_strings(&json.c[0], &json.c[1])?;
+        let a = parse_g1_point_from_strings(&json.a, "$.a")?;
+        let c = parse_g1_point_from_strings(&json.c, "$.c")?;
 
         // Parse point B (G2) using helper function
-        let b = parse_g2_point_from_coords(&json.b)?;
+        let b = parse_g2_point_from_coords(&json.b, "$.b")?;
 
         // Parse public inputs using helper function
         let mut public_inputs = Vec::new();
         for (i, input_str) in json.inputs.iter().enumerate() {
-            let el = parse_field_element(input_str)
-                .map_err(|e| anyhow!("Failed to parse input[{}]: {}", i, e))?;
+            let el = parse_prime_field(input_str, format!("$.inputs[{}]", i), "field element")?;
             public_inputs.push(el);
         }
 
+        // Named signals must be the same values as the inputs, in the same order
+        if let Some(public) = &json.public {
+            if public.len() != public_inputs.len() {
+                return Err(Error::json(
+                    "$.public",
+                    format!(
+                        "has {} signals, but there are {} inputs",
+                        public.len(),
+                        public_inputs.len()
+                    ),
+                ));
+            }
+            for ((name, value), input) in public.iter().zip(&public_inputs) {
+                let path = format!("$.public[{:?}]", name);
+                if parse_prime_field::<E::ScalarField>(value, path.clone(), "field element")?
+                    != *input
+                {
+                    return Err(Error::json(path, "doesn't match the inputs"));
+                }
+            }
+        }
+
         let proof = Proof { a, b, c };
         Ok((proof, public_inputs))
     }
 }
 
 // Main serialization/deserialization functions
-pub fn verifying_key_to_json(vk: &VerifyingKey<Bn254>) -> Result<String> {
-    let json_vk = VerifyingKeyJson::from(vk);
+
+/// Errors if the key has no IC points, not even the constant term
+pub fn verifying_key_to_json<E: CircomCurve>(vk: &VerifyingKey<E>) -> Result<String> {
+    let json_vk = VerifyingKeyJson::try_from(vk)?;
     Ok(serde_json::to_string_pretty(&json_vk)?)
 }
 
-pub fn verifying_key_from_json(json_str: &str) -> Result<VerifyingKey<Bn254>> {
-    let json_vk: VerifyingKeyJson = serde_json::from_str(json_str)?;
+/// Read a verifying key in this crate's format or as a snarkjs `verification_key.json`
+pub fn verifying_key_from_json<E: CircomCurve>(json_str: &str) -> Result<VerifyingKey<E>> {
+    let value: serde_json::Value = serde_json::from_str(json_str)?;
+    if is_snarkjs_verifying_key(&value) {
+        let json_vk: SnarkjsVerifyingKeyJson = serde_json::from_value(value)?;
+        return VerifyingKey::try_from(json_vk);
+    }
+    let json_vk: VerifyingKeyJson = serde_json::from_value(value)?;
     VerifyingKey::try_from(json_vk)
 }
 
-pub fn proof_to_json(proof: &Proof<Bn254>, public_inputs: &Vec<Fr>) -> Result<String> {
+pub fn proof_to_json<E: CircomCurve>(
+    proof: &Proof<E>,
+    public_inputs: &Vec<E::ScalarField>,
+) -> Result<String> {
     let json_proof = ProofJson::from((proof, public_inputs));
     Ok(serde_json::to_string_pretty(&json_proof)?)
 }
 
-pub fn proof_from_json(json_str: &str) -> Result<(Proof<Bn254>, Vec<Fr>)> {
+/// Same as [`proof_to_json`], also naming each public input under `public`
+pub fn proof_to_json_with_signals<E: CircomCurve>(
+    proof: &Proof<E>,
+    public_signals: &PublicSignals<E::ScalarField>,
+) -> Result<String> {
+    let public_inputs: Vec<E::ScalarField> = public_signals.values().copied().collect();
+    let mut json_proof = ProofJson::from((proof, &public_inputs));
+    json_proof.public = Some(
+        public_signals
+            .iter()
+            .map(|(name, value)| (name.clone(), value.to_string()))
+            .collect(),
+    );
+    Ok(serde_json::to_string_pretty(&json_proof)?)
+}
+
+pub fn proof_from_json<E: CircomCurve>(json_str: &str) -> Result<(Proof<E>, Vec<E::ScalarField>)> {
+    let json_proof: ProofJson = serde_json::from_str(json_str)?;
+    <(Proof<E>, Vec<E::ScalarField>)>::try_from(json_proof)
+}
+
+/// Read a proof with its named public signals, an error if it has none
+pub fn proof_from_json_with_signals<E: CircomCurve>(
+    json_str: &str,
+) -> Result<(Proof<E>, PublicSignals<E::ScalarField>)> {
     let json_proof: ProofJson = serde_json::from_str(json_str)?;
-    <(Proof<Bn254>, Vec<Fr>)>::try_from(json_proof)
+    let names: Vec<String> = match &json_proof.public {
+        Some(public) => public.keys().cloned().collect(),
+        None => return Err(Error::json("$.public", "missing")),
+    };
+    let (proof, public_inputs) = <(Proof<E>, Vec<E::ScalarField>)>::try_from(json_proof)?;
+    Ok((proof, names.into_iter().zip(public_inputs).collect()))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use ark_bn254::Bn254;
 
     #[test]
     fn test_verifying_key_deserialization() {
@@ -237,7 +332,7 @@ mod tests {
 	]
 }"#;
 
-        let result = verifying_key_from_json(vk_json);
+        let result = verifying_key_from_json::<Bn254>(vk_json);
         assert!(
             result.is_ok(),
             "Failed to deserialize verifying key: {:?}",
@@ -262,7 +357,7 @@ mod tests {
         );
 
         // Deserialize again and compare
-        let vk2_result = verifying_key_from_json(&serialized.unwrap());
+        let vk2_result = verifying_key_from_json::<Bn254>(&serialized.unwrap());
         assert!(
             vk2_result.is_ok(),
             "Failed to deserialize roundtrip verifying key: {:?}",
@@ -296,6 +391,38 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_verifying_key_ic_matches_n_public() {
+        let vk: VerifyingKey<Bn254> = crate::solidity::tests::example_proof().0;
+        let mut json: serde_json::Value =
+            serde_json::from_str(&verifying_key_to_json(&vk).unwrap()).unwrap();
+
+        json["nPublic"] = 3.into();
+        let err = verifying_key_from_json::<Bn254>(&json.to_string()).unwrap_err();
+        assert!(
+            matches!(err, Error::Json { ref path, .. } if path == "$.nPublic"),
+            "{}",
+            err
+        );
+
+        // No IC points at all, not even the constant term
+        json["nPublic"] = 0.into();
+        json["IC"] = serde_json::json!([]);
+        let err = verifying_key_from_json::<Bn254>(&json.to_string()).unwrap_err();
+        assert!(
+            matches!(err, Error::Json { ref path, .. } if path == "$.nPublic"),
+            "{}",
+            err
+        );
+
+        let mut empty = vk.clone();
+        empty.gamma_abc_g1.clear();
+        assert!(matches!(
+            verifying_key_to_json(&empty),
+            Err(Error::Mismatch(_))
+        ));
+    }
+
     #[test]
     fn test_proof_deserialization() {
         let proof_json = r#"{
@@ -311,7 +438,7 @@ mod tests {
 	]
 }"#;
 
-        let result = proof_from_json(proof_json);
+        let result = proof_from_json::<Bn254>(proof_json);
         assert!(
             result.is_ok(),
             "Failed to deserialize proof: {:?}",
@@ -336,7 +463,7 @@ mod tests {
         );
 
         // Deserialize again and compare
-        let (proof2, public_inputs2) = proof_from_json(&serialized.unwrap()).unwrap();
+        let (proof2, public_inputs2) = proof_from_json::<Bn254>(&serialized.unwrap()).unwrap();
 
         // Compare proof fields
         assert_eq!(proof.a, proof2.a, "Point A mismatch after roundtrip");
@@ -357,4 +484,104 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_proof_errors_locate_bad_value() {
+        let proof_json = r#"{
+	"protocol":"groth16",
+	"type":"proof",
+	"curve":"bn128",
+	"a": ["19801287090726837578044200885001440254402844668381646296109323380504107979463", "16223645295886758837836946460684489930824831996298592009362843244530537565095"],
+	"b": [["13227541383047502364817281770506826380994950495371717515327355831245222863808", "14419158160229828478156294188598564679024729352043826435394413630574520415635"], ["7701434206311278289343724947323209665008169752900822212424490783498470765028", "10170314160637616072929693052506642860784907058519406560969697126545938282647"]],
+	"c": ["15931542535481606335382873551011159013606998048559820372751278530993816517682", "9936785827820229135526754961312073112426012772106930225610536358663776965"],
+	"inputs": ["110", "eleven"]
+}"#;
+        match proof_from_json::<Bn254>(proof_json) {
+            Err(Error::Json { path, .. }) => assert_eq!(path, "$.inputs[1]"),
+            _ => panic!("expected a JSON error"),
+        }
+
+        let wrong_curve = proof_json.replace("bn128", "bls12381");
+        match proof_from_json::<Bn254>(&wrong_curve) {
+            Err(Error::Json { path, .. }) => assert_eq!(path, "$.curve"),
+            _ => panic!("expected a JSON error"),
+        }
+    }
+
+    #[cfg(feature = "bls12-381")]
+    #[test]
+    fn test_bls12_381_round_trip() {
+        use crate::curve::Curve;
+        use ark_bls12_381::{Bls12_381, Fr, G1Affine, G2Affine};
+        use ark_ec::AffineRepr;
+
+        let g1 = G1Affine::generator();
+        let g2 = G2Affine::generator();
+        let proof = Proof::<Bls12_381> {
+            a: g1,
+            b: g2,
+            c: (g1 * Fr::from(2u64)).into(),
+        };
+        let inputs = vec![Fr::from(33u64), -Fr::from(1u64)];
+
+        let json = proof_to_json(&proof, &inputs).unwrap();
+        assert_eq!(Curve::from_json(&json).unwrap().name(), "bls12381");
+        let (parsed, parsed_inputs) = proof_from_json::<Bls12_381>(&json).unwrap();
+        assert_eq!(parsed, proof);
+        assert_eq!(parsed_inputs, inputs);
+
+        let err = proof_from_json::<Bn254>(&json).unwrap_err();
+        assert!(matches!(err, Error::Json { ref path, .. } if path == "$.curve"));
+
+        let vk = VerifyingKey::<Bls12_381> {
+            alpha_g1: g1,
+            beta_g2: g2,
+            gamma_g2: g2,
+            delta_g2: (g2 * Fr::from(3u64)).into(),
+            gamma_abc_g1: vec![g1, G1Affine::identity()],
+        };
+        let vk_json = verifying_key_to_json(&vk).unwrap();
+        assert_eq!(verifying_key_from_json::<Bls12_381>(&vk_json).unwrap(), vk);
+    }
+
+    #[test]
+    fn proof_with_named_signals() {
+        use ark_bn254::{Fr, G1Affine, G2Affine};
+        use ark_ec::AffineRepr;
+
+        let proof = Proof::<Bn254> {
+            a: G1Affine::generator(),
+            b: G2Affine::generator(),
+            c: G1Affine::generator(),
+        };
+        let signals: PublicSignals<Fr> = [
+            ("c".to_string(), Fr::from(110)),
+            ("a".to_string(), Fr::from(11)),
+        ]
+        .into_iter()
+        .collect();
+        let json = proof_to_json_with_signals(&proof, &signals).unwrap();
+
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["public"]["c"], "110");
+        assert_eq!(value["inputs"][1], "11");
+
+        let (_, read) = proof_from_json_with_signals::<Bn254>(&json).unwrap();
+        assert_eq!(read.keys().collect::<Vec<_>>(), ["c", "a"]);
+        let (_, inputs) = proof_from_json::<Bn254>(&json).unwrap();
+        assert_eq!(inputs, vec![Fr::from(110), Fr::from(11)]);
+
+        let tampered = json.replace("\"a\": \"11\"", "\"a\": \"12\"");
+        assert_ne!(tampered, json);
+        let err = proof_from_json::<Bn254>(&tampered).unwrap_err();
+        assert!(
+            matches!(err, Error::Json { ref path, .. } if path == "$.public[\"a\"]"),
+            "{}",
+            err
+        );
+
+        let plain = proof_to_json(&proof, &vec![Fr::from(110)]).unwrap();
+        assert!(!plain.contains("public"));
+        assert!(proof_from_json_with_signals::<Bn254>(&plain).is_err());
+    }
 }
@@ -1,12 +1,47 @@
+// So `#[derive(CircomInputs)]` works within this crate's tests
+extern crate self as ark_circom_witnesscalc;
+
+mod binfile;
 pub mod circuit;
 pub mod constraints;
+pub mod curve;
+pub mod error;
+mod graph;
+pub mod inputs;
 pub mod jsonstructs;
+pub mod prover;
+pub mod qap;
 pub mod r1cs_reader;
+pub mod snarkjs;
+pub mod solidity;
+pub mod sym;
 pub mod utils;
+pub mod wtns;
+pub mod zkey;
 
-pub use circuit::CircomCircuit;
+#[cfg(feature = "derive")]
+pub use ark_circom_witnesscalc_derive::CircomInputs;
+pub use circuit::{CircomCircuit, CircomCircuitRef, UnsatisfiedConstraint};
+pub use curve::{CircomCurve, Curve};
+pub use error::{Error, Result};
+pub use inputs::{CircomInput, CircomInputs, InputValue, Inputs};
 pub use jsonstructs::{
-    proof_from_json, proof_to_json, verifying_key_from_json, verifying_key_to_json,
+    proof_from_json, proof_from_json_with_signals, proof_to_json, proof_to_json_with_signals,
+    verifying_key_from_json, verifying_key_to_json,
 };
+pub use prover::{Prover, TypedProver};
+pub use qap::{CircomReduction, Reduction};
 pub use r1cs_reader::R1CSFile;
-pub use utils::{proof_oneshot, verify_proof, verify_proof_json};
+pub use snarkjs::{
+    proof_from_snarkjs_json, proof_to_snarkjs_json, public_inputs_from_json, public_inputs_to_json,
+    verifying_key_from_snarkjs_json, verifying_key_to_snarkjs_json,
+};
+pub use solidity::{proof_to_calldata, proof_to_eip197, solidity_verifier};
+pub use sym::{PublicSignals, Symbol, SymbolTable};
+pub use utils::{
+    parse_field_element, proof_and_inputs_from_json, proof_from_witness, proof_oneshot,
+    proof_oneshot_with_inputs, proof_oneshot_with_reduction, setup_oneshot, verify_proof,
+    verify_proof_json, verify_proof_json_with_public, MissingInputs,
+};
+pub use wtns::{witness_from_wtns, witness_to_json, witness_to_wtns};
+pub use zkey::ZKey;
@@ -0,0 +1,490 @@
+use std::io::Cursor;
+use std::marker::PhantomData;
+
+use ark_bn254::Bn254;
+use ark_crypto_primitives::snark::SNARK;
+use ark_ec::pairing::Pairing;
+use ark_ff::One;
+use ark_groth16::r1cs_to_qap::{LibsnarkReduction, R1CSToQAP};
+use ark_groth16::{prepare_verifying_key, Groth16, PreparedVerifyingKey, Proof, ProvingKey};
+use ark_relations::r1cs::SynthesisError;
+use ark_serialize::{CanonicalDeserialize, Compress, Validate};
+use ark_std::rand::thread_rng;
+
+use crate::circuit::{CircomCircuitRef, UnsatisfiedConstraint};
+use crate::curve::CircomCurve;
+use crate::error::{Error, Result};
+use crate::graph::Graph;
+use crate::inputs::{CircomInputs, Inputs};
+use crate::qap::CircomReduction;
+use crate::r1cs_reader::{R1CSFile, R1CS};
+use crate::sym::{PublicSignals, SymbolTable};
+use crate::utils::{calc_witness_typed, evaluate_inputs, verify_prepared, MissingInputs};
+use crate::zkey::ZKey;
+
+/// A proof together with the public inputs it was made for
+pub type ProofWithInputs<E = Bn254> = (Proof<E>, Vec<<E as Pairing>::ScalarField>);
+
+/// Holds the parsed witness graph, R1CS and proving key for one circuit, so
+/// that repeated proofs don't pay for re-parsing the artifacts every time.
+///
+/// ```rust,ignore
+/// let prover = Prover::new(&PKEY_DATA, &GRAPH_DATA, &R1CS_DATA)?;
+/// let (proof, public_inputs) = prover.prove(&inputs_data)?;
+/// ```
+///
+/// The curve defaults to BN254, use e.g. `Prover::<Bls12_381>::new` for others.
+/// `QAP` must be the reduction the proving key was generated with.
+///
+/// The witness graph is optional, [`Prover::without_graph`] only proves
+/// witnesses computed elsewhere with [`Prover::prove_witness`].
+pub struct Prover<E: CircomCurve = Bn254, QAP: R1CSToQAP = LibsnarkReduction> {
+    graph: Option<Graph>,
+    symbols: Option<SymbolTable>,
+    missing_inputs: MissingInputs,
+    r1cs: R1CS<E::ScalarField>,
+    pkey: ProvingKey<E>,
+    pvk: PreparedVerifyingKey<E>,
+    reduction: PhantomData<QAP>,
+}
+
+impl<E: CircomCurve, QAP: R1CSToQAP> Prover<E, QAP> {
+    /// Every point of the proving key is checked to be on the curve and in
+    /// the prime order subgroup, see [`Prover::new_unchecked`] to skip this
+    pub fn new(pkey_data: &[u8], graph_data: &[u8], r1cs_data: &[u8]) -> Result<Self> {
+        Self::new_with_validation(pkey_data, Some(graph_data), r1cs_data, Validate::Yes)
+    }
+
+    /// Skips validating the proving key, which is much faster for large
+    /// circuits. Only use this for keys you generated yourself.
+    pub fn new_unchecked(pkey_data: &[u8], graph_data: &[u8], r1cs_data: &[u8]) -> Result<Self> {
+        Self::new_with_validation(pkey_data, Some(graph_data), r1cs_data, Validate::No)
+    }
+
+    /// A prover for witnesses computed elsewhere, e.g. read from a `.wtns`
+    /// file, see [`Prover::prove_witness`]. The proving key is validated.
+    pub fn without_graph(pkey_data: &[u8], r1cs_data: &[u8]) -> Result<Self> {
+        Self::new_with_validation(pkey_data, None, r1cs_data, Validate::Yes)
+    }
+
+    fn new_with_validation(
+        pkey_data: &[u8],
+        graph_data: Option<&[u8]>,
+        r1cs_data: &[u8],
+        validate: Validate,
+    ) -> Result<Self> {
+        let pkey =
+            ProvingKey::<E>::deserialize_with_mode(Cursor::new(pkey_data), Compress::No, validate)
+                .map_err(|source| Error::Key {
+                    kind: "proving key",
+                    source,
+                })?;
+        let r1cs = R1CSFile::new(Cursor::new(r1cs_data))?.try_into()?;
+        Self::from_parts(pkey, graph_data, r1cs)
+    }
+
+    fn from_parts(
+        pkey: ProvingKey<E>,
+        graph_data: Option<&[u8]>,
+        r1cs: R1CS<E::ScalarField>,
+    ) -> Result<Self> {
+        let graph = graph_data.map(Graph::new::<E::ScalarField>).transpose()?;
+        let pvk = prepare_verifying_key(&pkey.vk);
+
+        Ok(Prover {
+            graph,
+            symbols: None,
+            missing_inputs: MissingInputs::Error,
+            r1cs,
+            pkey,
+            pvk,
+            reduction: PhantomData,
+        })
+    }
+
+    /// Name the public signals of proofs with the signals from the circuit's
+    /// `.sym` file, see [`Prover::prove_with_signals`]. Errors if the witness
+    /// graph has an input the symbol table doesn't.
+    pub fn with_symbols(mut self, symbols: SymbolTable) -> Result<Self> {
+        if let Some(graph) = &self.graph {
+            symbols.check_inputs(graph.input_mapping.keys())?;
+        }
+        self.symbols = Some(symbols);
+        Ok(self)
+    }
+
+    /// Set input signals missing from the inputs JSON to zero, rather than
+    /// failing with [`Error::MissingInputs`]
+    pub fn allow_missing_inputs(mut self) -> Self {
+        self.missing_inputs = MissingInputs::Zero;
+        self
+    }
+
+    /// Check a typed inputs struct against the witness graph's inputs, so a
+    /// struct out of sync with the circuit fails with every mismatched signal
+    pub fn check_inputs<T: CircomInputs<E::ScalarField>>(&self) -> Result<()> {
+        T::check_signals(&self.graph()?.input_mapping)
+    }
+
+    /// A prover taking the inputs as `T`, checked against the witness graph
+    /// now rather than when the first proof is made, see [`Prover::check_inputs`]
+    pub fn with_inputs<T: CircomInputs<E::ScalarField>>(self) -> Result<TypedProver<T, E, QAP>> {
+        self.check_inputs::<T>()?;
+        Ok(TypedProver {
+            prover: self,
+            inputs: PhantomData,
+        })
+    }
+
+    pub fn symbols(&self) -> Option<&SymbolTable> {
+        self.symbols.as_ref()
+    }
+
+    /// Name the public inputs of a proof, an error without a symbol table
+    pub fn public_signals(
+        &self,
+        public_inputs: &[E::ScalarField],
+    ) -> Result<PublicSignals<E::ScalarField>> {
+        match &self.symbols {
+            Some(symbols) => symbols.public_signals(public_inputs),
+            None => Err(Error::Mismatch(
+                "the prover has no symbol table to name public signals".to_string(),
+            )),
+        }
+    }
+
+    pub fn proving_key(&self) -> &ProvingKey<E> {
+        &self.pkey
+    }
+
+    pub fn r1cs(&self) -> &R1CS<E::ScalarField> {
+        &self.r1cs
+    }
+
+    /// Evaluate the witness graph for the given JSON inputs, an error if the
+    /// prover was made without one
+    pub fn calc_witness(&self, inputs_data: &str) -> Result<Vec<E::ScalarField>> {
+        self.graph()?.evaluate(|nodes, input_mapping, signals| {
+            calc_witness_typed(
+                nodes,
+                inputs_data,
+                input_mapping,
+                signals,
+                self.missing_inputs,
+            )
+        })
+    }
+
+    /// Same as [`Prover::calc_witness`], with inputs built in Rust rather than JSON
+    pub fn calc_witness_inputs(
+        &self,
+        inputs: &Inputs<E::ScalarField>,
+    ) -> Result<Vec<E::ScalarField>> {
+        let inputs = inputs.to_graph_values()?;
+        self.graph()?.evaluate(|nodes, input_mapping, signals| {
+            evaluate_inputs(nodes, &inputs, input_mapping, signals, self.missing_inputs)
+        })
+    }
+
+    fn graph(&self) -> Result<&Graph> {
+        self.graph
+            .as_ref()
+            .ok_or_else(|| Error::Graph("the prover has no witness graph".to_string()))
+    }
+
+    /// Compute the witness for the inputs and return every constraint it fails
+    pub fn check_witness(
+        &self,
+        inputs_data: &str,
+    ) -> Result<Vec<UnsatisfiedConstraint<E::ScalarField>>> {
+        let witness = self.calc_witness(inputs_data)?;
+        let circom = CircomCircuitRef {
+            r1cs: &self.r1cs,
+            witness: Some(&witness),
+        };
+        Ok(circom.check_witness()?)
+    }
+
+    pub fn prove(&self, inputs_data: &str) -> Result<ProofWithInputs<E>> {
+        self.prove_witness(self.calc_witness(inputs_data)?)
+    }
+
+    /// Same as [`Prover::prove`], with inputs built in Rust rather than JSON
+    pub fn prove_inputs(&self, inputs: &Inputs<E::ScalarField>) -> Result<ProofWithInputs<E>> {
+        self.prove_witness(self.calc_witness_inputs(inputs)?)
+    }
+
+    /// Same as [`Prover::prove`], returning the public inputs by signal name,
+    /// which needs [`Prover::with_symbols`]
+    pub fn prove_with_signals(
+        &self,
+        inputs_data: &str,
+    ) -> Result<(Proof<E>, PublicSignals<E::ScalarField>)> {
+        let (proof, public_inputs) = self.prove(inputs_data)?;
+        Ok((proof, self.public_signals(&public_inputs)?))
+    }
+
+    /// Prove a witness computed elsewhere, e.g. by circom's witness generators.
+    /// It must have a value for every wire of the r1cs, starting with the constant 1.
+    pub fn prove_witness(&self, witness: Vec<E::ScalarField>) -> Result<ProofWithInputs<E>> {
+        if witness.len() != self.r1cs.num_variables {
+            return Err(Error::Mismatch(format!(
+                "the witness has {} values, the r1cs has {} wires",
+                witness.len(),
+                self.r1cs.num_variables
+            )));
+        }
+        if !witness[0].is_one() {
+            return Err(Error::Mismatch(
+                "the witness doesn't start with the constant 1".to_string(),
+            ));
+        }
+
+        let circom = CircomCircuitRef {
+            r1cs: &self.r1cs,
+            witness: Some(&witness),
+        };
+
+        let public_inputs = circom
+            .get_public_inputs()
+            .ok_or(Error::Synthesis(SynthesisError::AssignmentMissing))?;
+
+        // Cloning only copies the references, kept to find a broken constraint
+        let mut rng = thread_rng();
+        let proof = Groth16::<E, QAP>::prove(&self.pkey, circom.clone(), &mut rng)?;
+
+        // Verify the proof can be verified! If not, find out which constraint the witness breaks
+        if !verify_prepared(&self.pvk, &proof, &public_inputs)? {
+            return Err(match circom.check_witness()?.first() {
+                Some(unsatisfied) => Error::Unsatisfied {
+                    constraint: unsatisfied.index,
+                },
+                None => Error::Verification("generated proof does not verify".to_string()),
+            });
+        }
+
+        Ok((proof, public_inputs))
+    }
+
+    /// Prove each of the JSON inputs in turn, stopping at the first failure
+    pub fn prove_many<I, S>(&self, inputs: I) -> Result<Vec<ProofWithInputs<E>>>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        inputs
+            .into_iter()
+            .map(|inputs_data| self.prove(inputs_data.as_ref()))
+            .collect()
+    }
+}
+
+impl<E: CircomCurve> Prover<E, CircomReduction> {
+    /// Prove with the key from a snarkjs `.zkey`, so proofs are accepted by
+    /// snarkjs and its Solidity verifiers. The r1cs must be for the same circuit.
+    pub fn from_zkey(zkey_data: &[u8], graph_data: &[u8], r1cs_data: &[u8]) -> Result<Self> {
+        let zkey = ZKey::<E>::new(Cursor::new(zkey_data))?;
+        let r1cs = R1CSFile::new(Cursor::new(r1cs_data))?.try_into()?;
+        zkey.check_r1cs(&r1cs)?;
+        Self::from_parts(zkey.proving_key, Some(graph_data), r1cs)
+    }
+
+    /// [`Prover::from_zkey`] for witnesses computed elsewhere, see [`Prover::prove_witness`]
+    pub fn from_zkey_without_graph(zkey_data: &[u8], r1cs_data: &[u8]) -> Result<Self> {
+        let zkey = ZKey::<E>::new(Cursor::new(zkey_data))?;
+        let r1cs = R1CSFile::new(Cursor::new(r1cs_data))?.try_into()?;
+        zkey.check_r1cs(&r1cs)?;
+        Self::from_parts(zkey.proving_key, None, r1cs)
+    }
+}
+
+/// A [`Prover`] for one struct of circuit inputs, from [`Prover::with_inputs`]
+///
+/// ```rust,ignore
+/// #[derive(CircomInputs)]
+/// struct Multiply {
+///     a: Fr,
+///     b: Fr,
+/// }
+///
+/// let prover = Prover::new(&PKEY_DATA, &GRAPH_DATA, &R1CS_DATA)?.with_inputs::<Multiply>()?;
+/// let (proof, public_inputs) = prover.prove(&Multiply { a, b })?;
+/// ```
+pub struct TypedProver<T, E: CircomCurve = Bn254, QAP: R1CSToQAP = LibsnarkReduction> {
+    prover: Prover<E, QAP>,
+    inputs: PhantomData<fn(&T)>,
+}
+
+impl<T, E, QAP> TypedProver<T, E, QAP>
+where
+    T: CircomInputs<E::ScalarField>,
+    E: CircomCurve,
+    QAP: R1CSToQAP,
+{
+    pub fn calc_witness(&self, inputs: &T) -> Result<Vec<E::ScalarField>> {
+        self.prover.calc_witness_inputs(&inputs.to_inputs())
+    }
+
+    pub fn prove(&self, inputs: &T) -> Result<ProofWithInputs<E>> {
+        self.prover.prove_inputs(&inputs.to_inputs())
+    }
+
+    /// The untyped prover, e.g. for [`Prover::prove_witness`]
+    pub fn prover(&self) -> &Prover<E, QAP> {
+        &self.prover
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuit::tests::relabelled_multiply;
+    use crate::utils::setup_oneshot;
+    use crate::utils::tests::g2_outside_subgroup;
+    use ark_bn254::Fr;
+    use ark_serialize::CanonicalSerialize;
+
+    static GRAPH_DATA: &[u8] = include_bytes!("../example/multiply.graph");
+    static R1CS_DATA: &[u8] = include_bytes!("../example/multiply.r1cs");
+
+    #[test]
+    fn prove_with_graph() {
+        let pk = setup_oneshot::<Bn254, LibsnarkReduction>(R1CS_DATA).unwrap();
+        let mut pkey_data = Vec::new();
+        pk.serialize_uncompressed(&mut pkey_data).unwrap();
+        let prover = Prover::<Bn254>::new(&pkey_data, GRAPH_DATA, R1CS_DATA).unwrap();
+
+        // Wires are the constant 1, the output c, then the inputs a and b
+        let witness = prover.calc_witness(r#"{"a": "11", "b": "10"}"#).unwrap();
+        assert_eq!(witness, [1, 110, 11, 10].map(Fr::from));
+
+        let proofs = prover
+            .prove_many([r#"{"a": "11", "b": "10"}"#, r#"{"a": 3, "b": "0x7"}"#])
+            .unwrap();
+        assert_eq!(proofs.len(), 2);
+        assert_eq!(proofs[0].1, [Fr::from(110), Fr::from(11)]);
+        assert_eq!(proofs[1].1, [Fr::from(21), Fr::from(3)]);
+        for (proof, public_inputs) in &proofs {
+            assert!(Groth16::<Bn254>::verify(&pk.vk, public_inputs, proof).unwrap());
+        }
+        assert!(!Groth16::<Bn254>::verify(&pk.vk, &proofs[1].1, &proofs[0].0).unwrap());
+
+        assert!(matches!(
+            prover.prove(r#"{"a": "11"}"#),
+            Err(Error::MissingInputs(_))
+        ));
+    }
+
+    #[test]
+    fn witnesses_are_indexed_by_wire() {
+        // The witness is indexed by wire, as the label map only names the wires
+        let r1cs_data = relabelled_multiply();
+        let file = R1CSFile::<Fr>::new(Cursor::new(&r1cs_data)).unwrap();
+        assert_eq!(file.wire_mapping, [0, 2, 5, 6]);
+
+        let pk = setup_oneshot::<Bn254, LibsnarkReduction>(&r1cs_data).unwrap();
+        let mut pkey_data = Vec::new();
+        pk.serialize_uncompressed(&mut pkey_data).unwrap();
+        let prover = Prover::<Bn254>::new(&pkey_data, GRAPH_DATA, &r1cs_data).unwrap();
+        let (proof, public_inputs) = prover.prove(r#"{"a": "11", "b": "10"}"#).unwrap();
+        assert_eq!(public_inputs, [Fr::from(110), Fr::from(11)]);
+        assert!(Groth16::<Bn254>::verify(&pk.vk, &public_inputs, &proof).unwrap());
+    }
+
+    #[test]
+    fn proving_keys_are_validated() {
+        let mut pk = setup_oneshot::<Bn254, LibsnarkReduction>(R1CS_DATA).unwrap();
+        pk.b_g2_query[1] = g2_outside_subgroup();
+        let mut pkey_data = Vec::new();
+        pk.serialize_uncompressed(&mut pkey_data).unwrap();
+
+        assert!(matches!(
+            Prover::<Bn254>::new(&pkey_data, GRAPH_DATA, R1CS_DATA),
+            Err(Error::Key { .. })
+        ));
+        assert!(matches!(
+            Prover::<Bn254>::without_graph(&pkey_data, R1CS_DATA),
+            Err(Error::Key { .. })
+        ));
+        let prover = Prover::<Bn254>::new_unchecked(&pkey_data, GRAPH_DATA, R1CS_DATA).unwrap();
+        assert!(prover.proving_key() == &pk);
+    }
+
+    #[cfg(feature = "bls12-381")]
+    #[test]
+    fn prove_bls12_381() {
+        use crate::utils::verify_proof_json_with_public;
+        use crate::{proof_to_snarkjs_json, public_inputs_to_json, verifying_key_to_snarkjs_json};
+        use ark_bls12_381::{Bls12_381, Fr};
+
+        static R1CS_DATA: &[u8] = include_bytes!("../example/bls12381/multiply.r1cs");
+        let pk = setup_oneshot::<Bls12_381, CircomReduction>(R1CS_DATA).unwrap();
+        let mut pkey_data = Vec::new();
+        pk.serialize_uncompressed(&mut pkey_data).unwrap();
+
+        // No witness graph can hold the BLS12-381 prime
+        assert!(matches!(
+            Prover::<Bls12_381, CircomReduction>::new(&pkey_data, GRAPH_DATA, R1CS_DATA),
+            Err(Error::Graph(_))
+        ));
+
+        let prover =
+            Prover::<Bls12_381, CircomReduction>::without_graph(&pkey_data, R1CS_DATA).unwrap();
+        let (proof, public_inputs) = prover
+            .prove_witness([1, 110, 11, 10].map(Fr::from).to_vec())
+            .unwrap();
+        assert_eq!(public_inputs, [Fr::from(110), Fr::from(11)]);
+        assert!(Groth16::<Bls12_381>::verify(&pk.vk, &public_inputs, &proof).unwrap());
+        assert!(verify_proof_json_with_public::<Bls12_381>(
+            &verifying_key_to_snarkjs_json(&pk.vk).unwrap(),
+            &proof_to_snarkjs_json(&proof).unwrap(),
+            Some(&public_inputs_to_json(&public_inputs).unwrap()),
+        )
+        .unwrap());
+    }
+
+    #[cfg(feature = "derive")]
+    #[test]
+    fn typed_inputs_are_checked_when_loaded() {
+        use crate::CircomInputs;
+
+        #[derive(CircomInputs)]
+        struct Multiply {
+            a: Fr,
+            b: u64,
+        }
+
+        #[derive(CircomInputs)]
+        struct Wrong {
+            a: [Fr; 2],
+            c: Fr,
+        }
+
+        let pk = setup_oneshot::<Bn254, LibsnarkReduction>(R1CS_DATA).unwrap();
+        let mut pkey_data = Vec::new();
+        pk.serialize_uncompressed(&mut pkey_data).unwrap();
+        let new_prover = || Prover::<Bn254>::new(&pkey_data, GRAPH_DATA, R1CS_DATA).unwrap();
+
+        let prover = new_prover().with_inputs::<Multiply>().unwrap();
+        let (proof, public_inputs) = prover
+            .prove(&Multiply {
+                a: Fr::from(11),
+                b: 10,
+            })
+            .unwrap();
+        assert_eq!(public_inputs, [Fr::from(110), Fr::from(11)]);
+        assert!(Groth16::<Bn254>::verify(&pk.vk, &public_inputs, &proof).unwrap());
+
+        let Err(Error::InputShape(mismatches)) = new_prover().with_inputs::<Wrong>() else {
+            panic!("expected the wrong struct to be rejected");
+        };
+        assert_eq!(
+            mismatches,
+            [
+                "'a' has 2 values, the circuit expects 1",
+                "'c' isn't an input of the circuit",
+                "no field for 'b' (1 value)",
+            ]
+        );
+    }
+}
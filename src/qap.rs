@@ -0,0 +1,202 @@
+// From https://github.com/arkworks-rs/circom-compat/blob/3c95ed98e23a408b4d99a53e483a9bba39685a4e/src/circom/qap.rs
+// Without the rayon iterators, which aren't available in WASM builds
+
+use ark_ff::PrimeField;
+use ark_groth16::r1cs_to_qap::{evaluate_constraint, LibsnarkReduction, R1CSToQAP};
+use ark_poly::EvaluationDomain;
+use ark_relations::r1cs::{ConstraintMatrices, ConstraintSystemRef, SynthesisError};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::Error;
+
+/// Implements the witness map used by snarkjs. The arkworks witness map calculates the
+/// coefficients of H through computing (AB-C)/Z in the evaluation domain and going back to the
+/// coefficients domain. snarkjs instead precomputes the Lagrange form of the powers of tau bases
+/// in a domain twice as large and the witness map is computed as the odd coefficients of (AB-C)
+/// in that domain. This serves as HZ when computing the C proof element.
+///
+/// Proving keys from a snarkjs `.zkey` must be used with this reduction. C·w is
+/// taken to be A·w ∗ B·w, so the C matrix is never read.
+pub struct CircomReduction;
+
+impl R1CSToQAP for CircomReduction {
+    #[allow(clippy::type_complexity)]
+    fn instance_map_with_evaluation<F: PrimeField, D: EvaluationDomain<F>>(
+        cs: ConstraintSystemRef<F>,
+        t: &F,
+    ) -> Result<(Vec<F>, Vec<F>, Vec<F>, F, usize, usize), SynthesisError> {
+        LibsnarkReduction::instance_map_with_evaluation::<F, D>(cs, t)
+    }
+
+    fn witness_map_from_matrices<F: PrimeField, D: EvaluationDomain<F>>(
+        matrices: &ConstraintMatrices<F>,
+        num_inputs: usize,
+        num_constraints: usize,
+        full_assignment: &[F],
+    ) -> Result<Vec<F>, SynthesisError> {
+        let zero = F::zero();
+        let domain =
+            D::new(num_constraints + num_inputs).ok_or(SynthesisError::PolynomialDegreeTooLarge)?;
+        let domain_size = domain.size();
+
+        let mut a = vec![zero; domain_size];
+        let mut b = vec![zero; domain_size];
+
+        a[..num_constraints]
+            .iter_mut()
+            .zip(b[..num_constraints].iter_mut())
+            .zip(matrices.a.iter())
+            .zip(matrices.b.iter())
+            .for_each(|(((a, b), at_i), bt_i)| {
+                *a = evaluate_constraint(at_i, full_assignment);
+                *b = evaluate_constraint(bt_i, full_assignment);
+            });
+
+        {
+            let start = num_constraints;
+            let end = start + num_inputs;
+            a[start..end].clone_from_slice(&full_assignment[..num_inputs]);
+        }
+
+        let mut c = vec![zero; domain_size];
+        c[..num_constraints]
+            .iter_mut()
+            .zip(&a)
+            .zip(&b)
+            .for_each(|((c_i, &a), &b)| {
+                *c_i = a * b;
+            });
+
+        domain.ifft_in_place(&mut a);
+        domain.ifft_in_place(&mut b);
+
+        let root_of_unity = {
+            let domain_size_double = 2 * domain_size;
+            let domain_double =
+                D::new(domain_size_double).ok_or(SynthesisError::PolynomialDegreeTooLarge)?;
+            domain_double.element(1)
+        };
+        D::distribute_powers_and_mul_by_const(&mut a, root_of_unity, F::one());
+        D::distribute_powers_and_mul_by_const(&mut b, root_of_unity, F::one());
+
+        domain.fft_in_place(&mut a);
+        domain.fft_in_place(&mut b);
+
+        let mut ab = domain.mul_polynomials_in_evaluation_domain(&a, &b);
+        drop(a);
+        drop(b);
+
+        domain.ifft_in_place(&mut c);
+        D::distribute_powers_and_mul_by_const(&mut c, root_of_unity, F::one());
+        domain.fft_in_place(&mut c);
+
+        ab.iter_mut().zip(c).for_each(|(ab_i, c_i)| *ab_i -= &c_i);
+
+        Ok(ab)
+    }
+
+    fn h_query_scalars<F: PrimeField, D: EvaluationDomain<F>>(
+        max_power: usize,
+        t: F,
+        _: F,
+        delta_inverse: F,
+    ) -> Result<Vec<F>, SynthesisError> {
+        // the usual H query has domain-1 powers. Z has domain powers. So HZ has 2*domain-1 powers.
+        let mut scalars = (0..2 * max_power + 1)
+            .map(|i| delta_inverse * t.pow([i as u64]))
+            .collect::<Vec<_>>();
+        let domain_size = scalars.len();
+        let domain = D::new(domain_size).ok_or(SynthesisError::PolynomialDegreeTooLarge)?;
+        // generate the lagrange coefficients
+        domain.ifft_in_place(&mut scalars);
+        Ok(scalars.into_iter().skip(1).step_by(2).collect())
+    }
+}
+
+/// Runtime choice of QAP reduction, e.g. from a command-line flag. Proving
+/// must use the same reduction that the proving key was generated with.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Reduction {
+    /// [`LibsnarkReduction`], the arkworks default
+    #[default]
+    Libsnark,
+    /// [`CircomReduction`], compatible with snarkjs and rapidsnark
+    Circom,
+}
+
+impl Reduction {
+    pub fn name(self) -> &'static str {
+        match self {
+            Reduction::Libsnark => "libsnark",
+            Reduction::Circom => "circom",
+        }
+    }
+}
+
+impl fmt::Display for Reduction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Reduction {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        match s.to_ascii_lowercase().as_str() {
+            "libsnark" | "arkworks" => Ok(Reduction::Libsnark),
+            "circom" | "snarkjs" => Ok(Reduction::Circom),
+            _ => Err(Error::UnsupportedReduction(s.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuit::CircomCircuit;
+    use crate::r1cs_reader::R1CSFile;
+    use crate::utils::setup_oneshot;
+    use ark_bn254::{Bn254, Fr};
+    use ark_crypto_primitives::snark::SNARK;
+    use ark_groth16::{Groth16, ProvingKey};
+    use ark_std::io::Cursor;
+    use ark_std::rand::thread_rng;
+
+    static R1CS_DATA: &[u8] = include_bytes!("../example/multiply.r1cs");
+
+    fn prove_and_verify<QAP: R1CSToQAP>(pk: &ProvingKey<Bn254>) -> bool {
+        let circuit = CircomCircuit::<Fr> {
+            r1cs: R1CSFile::new(Cursor::new(R1CS_DATA))
+                .unwrap()
+                .try_into()
+                .unwrap(),
+            witness: Some([1, 110, 11, 10].map(Fr::from).to_vec()),
+        };
+        let inputs = circuit.get_public_inputs().unwrap();
+        let proof = Groth16::<Bn254, QAP>::prove(pk, circuit, &mut thread_rng()).unwrap();
+        Groth16::<Bn254, QAP>::verify(&pk.vk, &inputs, &proof).unwrap()
+    }
+
+    #[test]
+    fn reductions_must_match_the_key() {
+        let libsnark = setup_oneshot::<Bn254, LibsnarkReduction>(R1CS_DATA).unwrap();
+        let circom = setup_oneshot::<Bn254, CircomReduction>(R1CS_DATA).unwrap();
+
+        assert!(prove_and_verify::<LibsnarkReduction>(&libsnark));
+        assert!(prove_and_verify::<CircomReduction>(&circom));
+        assert!(!prove_and_verify::<LibsnarkReduction>(&circom));
+        assert!(!prove_and_verify::<CircomReduction>(&libsnark));
+    }
+
+    #[test]
+    fn parse_reduction() {
+        assert_eq!("snarkjs".parse::<Reduction>().unwrap(), Reduction::Circom);
+        assert_eq!(
+            "Libsnark".parse::<Reduction>().unwrap(),
+            Reduction::Libsnark
+        );
+        assert!("groth16".parse::<Reduction>().is_err());
+    }
+}
@@ -8,17 +8,12 @@
 // From: https://github.com/arkworks-rs/circom-compat/blob/3c95ed98e23a408b4d99a53e483a9bba39685a4e/src/circom/r1cs_reader.rs
 
 use ark_ff::PrimeField;
-use byteorder::{LittleEndian, ReadBytesExt};
-use std::io::{Error, ErrorKind};
 
-use ark_serialize::{SerializationError, SerializationError::IoError};
-use ark_std::io::{Read, Seek, SeekFrom};
-
-use std::collections::HashMap;
-
-type IoResult<T> = Result<T, SerializationError>;
+use ark_std::io::{Read, Seek};
 
+use crate::binfile::{OffsetReader, Sections};
 use crate::constraints::{ConstraintVec, Constraints};
+use crate::error::{Error, Result};
 
 #[derive(Clone, Debug)]
 pub struct R1CS<F> {
@@ -26,21 +21,41 @@ pub struct R1CS<F> {
     pub num_aux: usize,
     pub num_variables: usize,
     pub constraints: Vec<Constraints<F>>,
-    pub wire_mapping: Option<Vec<usize>>,
 }
 
-impl<F: PrimeField> From<R1CSFile<F>> for R1CS<F> {
-    fn from(file: R1CSFile<F>) -> Self {
+/// Fails if the circuit applies custom gates, as their constraints aren't in
+/// the r1cs and a Groth16 key made without them would be for an incomplete system
+impl<F: PrimeField> TryFrom<R1CSFile<F>> for R1CS<F> {
+    type Error = Error;
+
+    fn try_from(file: R1CSFile<F>) -> Result<Self> {
+        if !file.custom_gates_applied.is_empty() {
+            // Only the gates applied, each named once
+            let mut applied: Vec<u32> = file
+                .custom_gates_applied
+                .iter()
+                .map(|application| application.custom_gate)
+                .collect();
+            applied.sort_unstable();
+            applied.dedup();
+            return Err(Error::CustomGates(
+                applied
+                    .iter()
+                    .filter_map(|i| file.custom_gates_used.get(*i as usize))
+                    .map(|gate| gate.template_name.clone())
+                    .collect(),
+            ));
+        }
+
         let num_inputs = (1 + file.header.n_pub_in + file.header.n_pub_out) as usize;
         let num_variables = file.header.n_wires as usize;
         let num_aux = num_variables - num_inputs;
-        R1CS {
+        Ok(R1CS {
             num_aux,
             num_inputs,
             num_variables,
             constraints: file.constraints,
-            wire_mapping: Some(file.wire_mapping.iter().map(|e| *e as usize).collect()),
-        }
+        })
     }
 }
 
@@ -49,6 +64,23 @@ pub struct R1CSFile<F: PrimeField> {
     pub header: Header,
     pub constraints: Vec<Constraints<F>>,
     pub wire_mapping: Vec<u64>,
+    pub custom_gates_used: Vec<CustomGate<F>>,
+    pub custom_gates_applied: Vec<CustomGateApplication>,
+}
+
+/// A custom template declared with `pragma custom_templates`, from section 4
+#[derive(Clone, Debug)]
+pub struct CustomGate<F> {
+    pub template_name: String,
+    pub parameters: Vec<F>,
+}
+
+/// One use of a custom gate from section 5, `custom_gate` indexes into
+/// [`R1CSFile::custom_gates_used`]
+#[derive(Clone, Debug)]
+pub struct CustomGateApplication {
+    pub custom_gate: u32,
+    pub signals: Vec<u64>,
 }
 
 impl<F: PrimeField> R1CSFile<F> {
@@ -57,97 +89,56 @@ impl<F: PrimeField> R1CSFile<F> {
     /// ```rust,ignore
     /// let reader = BufReader::new(Cursor::new(&data[..]));
     /// ```
-    pub fn new<R: Read + Seek>(mut reader: R) -> IoResult<R1CSFile<F>> {
-        let mut magic = [0u8; 4];
-        reader.read_exact(&mut magic)?;
-        if magic != [0x72, 0x31, 0x63, 0x73] {
-            return Err(IoError(Error::new(
-                ErrorKind::InvalidData,
-                "Invalid magic number",
-            )));
-        }
-
-        let version = reader.read_u32::<LittleEndian>()?;
-        if version != 1 {
-            return Err(IoError(Error::new(
-                ErrorKind::InvalidData,
-                "Unsupported version",
-            )));
-        }
-
-        let num_sections = reader.read_u32::<LittleEndian>()?;
-
-        // todo: handle sec_size correctly
-        // section type -> file offset
-        let mut sec_offsets = HashMap::<u32, u64>::new();
-        let mut sec_sizes = HashMap::<u32, u64>::new();
-
-        // get file offset of each section
-        for _ in 0..num_sections {
-            let sec_type = reader.read_u32::<LittleEndian>()?;
-            let sec_size = reader.read_u64::<LittleEndian>()?;
-            let offset = reader.stream_position()?;
-            sec_offsets.insert(sec_type, offset);
-            sec_sizes.insert(sec_type, sec_size);
-            reader.seek(SeekFrom::Current(sec_size as i64))?;
-        }
+    pub fn new<R: Read + Seek>(reader: R) -> Result<R1CSFile<F>> {
+        let mut reader =
+            OffsetReader::new(reader, |offset, message| Error::R1cs { offset, message });
+        let sections = Sections::read(&mut reader, b"r1cs", 1)?;
+        let version = 1;
 
         let header_type = 1;
         let constraint_type = 2;
         let wire2label_type = 3;
-
-        let header_offset = sec_offsets.get(&header_type).ok_or_else(|| {
-            Error::new(
-                ErrorKind::InvalidData,
-                "No section offset for header type found",
-            )
-        });
-
-        reader.seek(SeekFrom::Start(*header_offset?))?;
-
-        let header_size = sec_sizes.get(&header_type).ok_or_else(|| {
-            Error::new(
-                ErrorKind::InvalidData,
-                "No section size for header type found",
-            )
-        });
-
-        let header = Header::new(&mut reader, *header_size?)?;
-
-        let constraint_offset = sec_offsets.get(&constraint_type).ok_or_else(|| {
-            Error::new(
-                ErrorKind::InvalidData,
-                "No section offset for constraint type found",
-            )
-        });
-
-        reader.seek(SeekFrom::Start(*constraint_offset?))?;
-
-        let constraints = read_constraints::<&mut R, F>(&mut reader, &header)?;
-
-        let wire2label_offset = sec_offsets.get(&wire2label_type).ok_or_else(|| {
-            Error::new(
-                ErrorKind::InvalidData,
-                "No section offset for wire2label type found",
-            )
-        });
-
-        reader.seek(SeekFrom::Start(*wire2label_offset?))?;
-
-        let wire2label_size = sec_sizes.get(&wire2label_type).ok_or_else(|| {
-            Error::new(
-                ErrorKind::InvalidData,
-                "No section size for wire2label type found",
-            )
-        });
-
-        let wire_mapping = read_map(&mut reader, *wire2label_size?, &header)?;
+        let custom_gates_used_type = 4;
+        let custom_gates_applied_type = 5;
+
+        let header_size = sections.seek(&mut reader, header_type, "header")?;
+        let header = Header::new::<R, F>(&mut reader, header_size)?;
+
+        let constraint_size = sections.seek(&mut reader, constraint_type, "constraint")?;
+        let constraints = read_constraints::<R, F>(&mut reader, constraint_size, &header)?;
+
+        let wire2label_size = sections.seek(&mut reader, wire2label_type, "wire2label")?;
+        let wire_mapping = read_map(&mut reader, wire2label_size, &header)?;
+
+        // Custom gate sections are only present when compiled with custom templates
+        let custom_gates_used = match sections.contains(custom_gates_used_type) {
+            true => {
+                let size =
+                    sections.seek(&mut reader, custom_gates_used_type, "custom gates list")?;
+                read_custom_gates_used(&mut reader, size, &header)?
+            }
+            false => vec![],
+        };
+
+        let custom_gates_applied = match sections.contains(custom_gates_applied_type) {
+            true => {
+                let size = sections.seek(
+                    &mut reader,
+                    custom_gates_applied_type,
+                    "custom gates application",
+                )?;
+                read_custom_gates_applied(&mut reader, size, custom_gates_used.len(), &header)?
+            }
+            false => vec![],
+        };
 
         Ok(R1CSFile {
             version,
             header,
             constraints,
             wire_mapping,
+            custom_gates_used,
+            custom_gates_applied,
         })
     }
 }
@@ -164,100 +155,205 @@ pub struct Header {
 }
 
 impl Header {
-    fn new<R: Read>(mut reader: R, size: u64) -> IoResult<Header> {
-        let field_size = reader.read_u32::<LittleEndian>()?;
-        if field_size != 32 {
-            return Err(IoError(Error::new(
-                ErrorKind::InvalidData,
-                "This parser only supports 32-byte fields",
-            )));
+    /// Reads the header, checking the prime matches the modulus of `F`
+    fn new<R: Read + Seek, F: PrimeField>(
+        reader: &mut OffsetReader<R>,
+        size: u64,
+    ) -> Result<Header> {
+        let field_size = reader.read_u32()?;
+        if field_size == 0 || field_size % 8 != 0 {
+            return Err(reader.error(format!("Invalid field size {}", field_size)));
         }
 
         if size != 32 + field_size as u64 {
-            return Err(IoError(Error::new(
-                ErrorKind::InvalidData,
-                "Invalid header section size",
-            )));
+            return Err(reader.error("Invalid header section size"));
         }
 
-        let mut prime_size = vec![0u8; field_size as usize];
-        reader.read_exact(&mut prime_size)?;
-
-        if prime_size
-            != hex::decode("010000f093f5e1439170b97948e833285d588181b64550b829a031e1724e6430")
-                .unwrap()
-        {
-            return Err(IoError(Error::new(
-                ErrorKind::InvalidData,
-                "This parser only supports bn256",
-            )));
-        }
+        let prime_size = reader.read_prime::<F>(field_size)?;
 
-        Ok(Header {
+        let header = Header {
             field_size,
             prime_size,
-            n_wires: reader.read_u32::<LittleEndian>()?,
-            n_pub_out: reader.read_u32::<LittleEndian>()?,
-            n_pub_in: reader.read_u32::<LittleEndian>()?,
-            n_prv_in: reader.read_u32::<LittleEndian>()?,
-            n_labels: reader.read_u64::<LittleEndian>()?,
-            n_constraints: reader.read_u32::<LittleEndian>()?,
-        })
+            n_wires: reader.read_u32()?,
+            n_pub_out: reader.read_u32()?,
+            n_pub_in: reader.read_u32()?,
+            n_prv_in: reader.read_u32()?,
+            n_labels: reader.read_u64()?,
+            n_constraints: reader.read_u32()?,
+        };
+
+        // Wire 0 is always the constant one, followed by the public signals
+        if 1 + header.n_pub_out as u64 + header.n_pub_in as u64 > header.n_wires as u64 {
+            return Err(reader.error("More public signals than wires"));
+        }
+
+        Ok(header)
     }
 }
 
-fn read_constraint_vec<R: Read, F: PrimeField>(mut reader: R) -> IoResult<ConstraintVec<F>> {
-    let n_vec = reader.read_u32::<LittleEndian>()? as usize;
-    let mut vec = Vec::with_capacity(n_vec);
+fn read_constraint_vec<R: Read + Seek, F: PrimeField>(
+    reader: &mut OffsetReader<R>,
+    size: u64,
+    header: &Header,
+) -> Result<ConstraintVec<F>> {
+    let n_vec = reader.read_u32()? as usize;
+    if n_vec as u64 * (4 + header.field_size as u64) > size {
+        return Err(reader.error("Linear combination larger than constraint section"));
+    }
+    let mut vec = Vec::new();
     for _ in 0..n_vec {
-        vec.push((
-            reader.read_u32::<LittleEndian>()? as usize,
-            F::deserialize_uncompressed(&mut reader)?,
-        ));
+        let wire = reader.read_u32()?;
+        if wire >= header.n_wires {
+            return Err(reader.error(format!(
+                "term {} references wire {}, but there are only {} wires",
+                vec.len(),
+                wire,
+                header.n_wires
+            )));
+        }
+        vec.push((wire as usize, reader.read_field::<F>(header.field_size)?));
     }
     Ok(vec)
 }
 
-fn read_constraints<R: Read, F: PrimeField>(
-    mut reader: R,
+fn read_constraints<R: Read + Seek, F: PrimeField>(
+    reader: &mut OffsetReader<R>,
+    size: u64,
     header: &Header,
-) -> IoResult<Vec<Constraints<F>>> {
-    // todo check section size
-    let mut vec = Vec::with_capacity(header.n_constraints as usize);
-    for _ in 0..header.n_constraints {
-        vec.push((
-            read_constraint_vec::<&mut R, F>(&mut reader)?,
-            read_constraint_vec::<&mut R, F>(&mut reader)?,
-            read_constraint_vec::<&mut R, F>(&mut reader)?,
-        ));
+) -> Result<Vec<Constraints<F>>> {
+    // Every constraint has at least the three linear combination lengths
+    if header.n_constraints as u64 * 12 > size {
+        return Err(reader.error("Invalid constraint section size"));
+    }
+    // Counts from the file aren't preallocated, a crafted file could claim
+    // billions and abort the process rather than fail to read them
+    let mut vec = Vec::new();
+    for i in 0..header.n_constraints {
+        let read_lc = |reader: &mut OffsetReader<R>| {
+            read_constraint_vec::<R, F>(reader, size, header).map_err(|e| match e {
+                Error::R1cs { offset, message } => Error::R1cs {
+                    offset,
+                    message: format!("Constraint {}: {}", i, message),
+                },
+                e => e,
+            })
+        };
+        vec.push((read_lc(reader)?, read_lc(reader)?, read_lc(reader)?));
     }
     Ok(vec)
 }
 
-fn read_map<R: Read>(mut reader: R, size: u64, header: &Header) -> IoResult<Vec<u64>> {
+fn read_map<R: Read + Seek>(
+    reader: &mut OffsetReader<R>,
+    size: u64,
+    header: &Header,
+) -> Result<Vec<u64>> {
     if size != header.n_wires as u64 * 8 {
-        return Err(IoError(Error::new(
-            ErrorKind::InvalidData,
-            "Invalid map section size",
-        )));
+        return Err(reader.error("Invalid map section size"));
     }
-    let mut vec = Vec::with_capacity(header.n_wires as usize);
+    let mut vec = Vec::new();
     for _ in 0..header.n_wires {
-        vec.push(reader.read_u64::<LittleEndian>()?);
+        vec.push(reader.read_u64()?);
     }
     if vec[0] != 0 {
-        return Err(IoError(Error::new(
-            ErrorKind::InvalidData,
-            "Wire 0 should always be mapped to 0",
-        )));
+        return Err(reader.error("Wire 0 should always be mapped to 0"));
     }
     Ok(vec)
 }
 
+fn read_custom_gates_used<R: Read + Seek, F: PrimeField>(
+    reader: &mut OffsetReader<R>,
+    size: u64,
+    header: &Header,
+) -> Result<Vec<CustomGate<F>>> {
+    let n_gates = reader.read_u32()?;
+    // Every gate has at least a terminating null and its parameter count
+    if n_gates as u64 * 5 > size {
+        return Err(reader.error("Invalid custom gates list section size"));
+    }
+    let mut gates = Vec::new();
+    for _ in 0..n_gates {
+        let mut name = Vec::new();
+        loop {
+            let mut byte = [0u8];
+            reader.read_exact(&mut byte)?;
+            if byte[0] == 0 {
+                break;
+            }
+            if name.len() as u64 >= size {
+                return Err(reader.error("Unterminated custom gate name"));
+            }
+            name.push(byte[0]);
+        }
+        let template_name = String::from_utf8(name)
+            .map_err(|_| reader.error("Custom gate name is not valid UTF-8"))?;
+
+        let n_parameters = reader.read_u32()?;
+        if n_parameters as u64 * header.field_size as u64 > size {
+            return Err(reader.error("Custom gate has more parameters than fit in its section"));
+        }
+        let mut parameters = Vec::new();
+        for _ in 0..n_parameters {
+            parameters.push(reader.read_field::<F>(header.field_size)?);
+        }
+        gates.push(CustomGate {
+            template_name,
+            parameters,
+        });
+    }
+    Ok(gates)
+}
+
+fn read_custom_gates_applied<R: Read + Seek>(
+    reader: &mut OffsetReader<R>,
+    size: u64,
+    n_gates: usize,
+    header: &Header,
+) -> Result<Vec<CustomGateApplication>> {
+    let n_applications = reader.read_u32()?;
+    // Every application has at least the gate index and signal count
+    if n_applications as u64 * 8 > size {
+        return Err(reader.error("Invalid custom gates application section size"));
+    }
+    let mut applications = Vec::new();
+    for _ in 0..n_applications {
+        let custom_gate = reader.read_u32()?;
+        if custom_gate as usize >= n_gates {
+            return Err(reader.error(format!(
+                "Custom gate application references gate {}, but only {} are declared",
+                custom_gate, n_gates
+            )));
+        }
+        let n_signals = reader.read_u32()?;
+        if n_signals as u64 * 8 > size {
+            return Err(
+                reader.error("Custom gate application has more signals than fit in its section")
+            );
+        }
+        let mut signals = Vec::new();
+        for _ in 0..n_signals {
+            let signal = reader.read_u64()?;
+            if signal >= header.n_wires as u64 {
+                return Err(reader.error(format!(
+                    "Custom gate application references wire {}, but there are only {} wires",
+                    signal, header.n_wires
+                )));
+            }
+            signals.push(signal);
+        }
+        applications.push(CustomGateApplication {
+            custom_gate,
+            signals,
+        });
+    }
+    Ok(applications)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use ark_bn254::Fr;
+    use ark_bn254::{Fq, Fr};
+    use ark_ff::BigInteger;
     use ark_std::io::{BufReader, Cursor};
 
     #[test]
@@ -342,4 +438,116 @@ mod tests {
         assert_eq!(file.wire_mapping.len(), 7);
         assert_eq!(file.wire_mapping[1], 3);
     }
+
+    #[test]
+    fn corrupt_files_are_errors() {
+        let data = include_bytes!("../example/multiply.r1cs");
+        assert!(R1CSFile::<Fr>::new(Cursor::new(&data[..])).is_ok());
+
+        // Truncated file
+        assert!(R1CSFile::<Fr>::new(Cursor::new(&data[..data.len() - 8])).is_err());
+
+        // Constraint referencing wire 9, but there are only 4 wires
+        let mut bad_wire = data.to_vec();
+        bad_wire[0x1c] = 9;
+        match R1CSFile::<Fr>::new(Cursor::new(&bad_wire[..])) {
+            Err(Error::R1cs { offset, message }) => {
+                assert_eq!(offset, 0x20);
+                assert!(message.starts_with("Constraint 0:"), "{}", message);
+            }
+            _ => panic!("expected an r1cs format error"),
+        }
+
+        // Header claims a single wire, which can't hold the public signals
+        let mut bad_header = data.to_vec();
+        bad_header[0xc0] = 1;
+        assert!(R1CSFile::<Fr>::new(Cursor::new(&bad_header[..])).is_err());
+
+        // Billions of constraints, in a section past the end of the file or
+        // too small for them
+        let mut huge = data.to_vec();
+        huge[0xd8..0xdc].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(R1CSFile::<Fr>::new(Cursor::new(&huge[..])).is_err());
+        huge[0x10..0x18].copy_from_slice(&(u32::MAX as u64 * 12).to_le_bytes());
+        match R1CSFile::<Fr>::new(Cursor::new(&huge[..])) {
+            Err(Error::R1cs { message, .. }) => assert!(message.contains("past the end")),
+            _ => panic!("expected a section past the end of the file"),
+        }
+    }
+
+    #[test]
+    fn custom_gates() {
+        let mut data = include_bytes!("../example/multiply.r1cs").to_vec();
+        data[8] = 5; // Two more sections
+
+        // Section 4: a gate named "Gate" with a single parameter of 7, and
+        // one named "Unused" without parameters
+        data.extend_from_slice(&hex_literal::hex!("04000000 3800000000000000 02000000"));
+        data.extend_from_slice(b"Gate\0");
+        data.extend_from_slice(&hex_literal::hex!("01000000"));
+        data.extend_from_slice(&Fr::from(7).into_bigint().to_bytes_le());
+        data.extend_from_slice(b"Unused\0");
+        data.extend_from_slice(&hex_literal::hex!("00000000"));
+
+        // Section 5: the gate applied to signals 2 and 3
+        data.extend_from_slice(&hex_literal::hex!(
+            "05000000 1c00000000000000 01000000 00000000 02000000"
+        ));
+        data.extend_from_slice(&hex_literal::hex!("0200000000000000 0300000000000000"));
+
+        let file = R1CSFile::<Fr>::new(Cursor::new(&data[..])).unwrap();
+        assert_eq!(file.custom_gates_used.len(), 2);
+        assert_eq!(file.custom_gates_used[0].template_name, "Gate");
+        assert_eq!(file.custom_gates_used[1].template_name, "Unused");
+        assert_eq!(file.custom_gates_used[0].parameters, vec![Fr::from(7)]);
+        assert_eq!(file.custom_gates_applied.len(), 1);
+        assert_eq!(file.custom_gates_applied[0].custom_gate, 0);
+        assert_eq!(file.custom_gates_applied[0].signals, vec![2, 3]);
+
+        match R1CS::try_from(file) {
+            Err(Error::CustomGates(names)) => assert_eq!(names, vec!["Gate".to_string()]),
+            _ => panic!("expected custom gates to be rejected"),
+        }
+
+        // Billions of gates, or of signals in an application, are errors
+        let gates = 264 + 12;
+        let signals = gates + 0x38 + 12 + 8;
+        for offset in [gates, signals] {
+            let mut huge = data.clone();
+            huge[offset..offset + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+            assert!(R1CSFile::<Fr>::new(Cursor::new(&huge[..])).is_err());
+        }
+
+        // An application to wire 9, but there are only 4 wires
+        let mut bad_signal = data.clone();
+        bad_signal[signals + 4] = 9;
+        match R1CSFile::<Fr>::new(Cursor::new(&bad_signal[..])) {
+            Err(Error::R1cs { message, .. }) => assert_eq!(
+                message,
+                "Custom gate application references wire 9, but there are only 4 wires"
+            ),
+            _ => panic!("expected an r1cs format error"),
+        }
+    }
+
+    #[test]
+    fn other_primes() {
+        // Replace the prime in the header with the BN254 base field modulus
+        let mut data = include_bytes!("../example/multiply.r1cs").to_vec();
+        let modulus = Fq::MODULUS.to_bytes_le();
+        data[0xa0..0xc0].copy_from_slice(&modulus);
+
+        // Coefficients of -1 in the scalar field are valid elements of the base field
+        let file = R1CSFile::<Fq>::new(Cursor::new(&data[..])).unwrap();
+        assert_eq!(file.header.prime_size, modulus);
+        assert_eq!(file.constraints[0].1[0].1, Fq::from(1));
+
+        // But the header prime must match the requested field
+        match R1CSFile::<Fr>::new(Cursor::new(&data[..])) {
+            Err(Error::R1cs { offset, .. }) => assert_eq!(offset, 0xc0),
+            _ => panic!("expected prime mismatch"),
+        }
+        let data = include_bytes!("../example/multiply.r1cs");
+        assert!(R1CSFile::<Fq>::new(Cursor::new(&data[..])).is_err());
+    }
 }
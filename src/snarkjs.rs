@@ -0,0 +1,465 @@
+//! The JSON files written by snarkjs: `proof.json` and `verification_key.json`,
+//! with projective points and G2 coordinates as `[c0, c1]`, and `public.json`,
+//! a list of the public signals.
+
+use ark_ec::short_weierstrass::{Affine, SWCurveConfig};
+use ark_ec::AffineRepr;
+use ark_ff::{Field, PrimeField, Zero};
+use ark_groth16::{Proof, VerifyingKey};
+use serde::{Deserialize, Serialize};
+
+use crate::curve::CircomCurve;
+use crate::error::{Error, Result};
+use crate::jsonstructs::{check_tag, parse_prime_field, point_from_coords};
+use crate::utils::n_public;
+
+#[derive(Serialize, Deserialize)]
+pub struct SnarkjsProofJson {
+    pi_a: [String; 3],
+    pi_b: [[String; 2]; 3],
+    pi_c: [String; 3],
+    protocol: String,
+    curve: String,
+}
+
+/// `vk_alphabeta_12`, an element of the degree 12 extension field as
+/// `[c0, c1]` over the cubic then the quadratic extension
+type Fq12Json = [[[String; 2]; 3]; 2];
+
+#[derive(Serialize, Deserialize)]
+pub struct SnarkjsVerifyingKeyJson {
+    protocol: String,
+    curve: String,
+    #[serde(rename = "nPublic")]
+    n_public: usize,
+    vk_alpha_1: [String; 3],
+    vk_beta_2: [[String; 2]; 3],
+    vk_gamma_2: [[String; 2]; 3],
+    vk_delta_2: [[String; 2]; 3],
+    /// Optional when reading, as it can be recomputed from alpha and beta
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    vk_alphabeta_12: Option<Fq12Json>,
+    #[serde(rename = "IC")]
+    ic: Vec<[String; 3]>,
+}
+
+/// Serialize the way snarkjs does, `JSON.stringify(value, null, 1)`
+pub(crate) fn to_snarkjs_string<T: Serialize>(value: &T) -> Result<String> {
+    let mut out = Vec::new();
+    let formatter = serde_json::ser::PrettyFormatter::with_indent(b" ");
+    let mut ser = serde_json::Serializer::with_formatter(&mut out, formatter);
+    value.serialize(&mut ser)?;
+    // serde_json only writes valid UTF-8
+    Ok(String::from_utf8_lossy(&out).into_owned())
+}
+
+/// Base prime field limbs of a coordinate, `c0` first
+fn limbs<F: Field>(f: &F) -> Vec<String> {
+    f.to_base_prime_field_elements()
+        .map(|c| c.to_string())
+        .collect()
+}
+
+/// `[x, y, z]` with `z = 1`, or `[0, 1, 0]` for the point at infinity
+fn projective<P: SWCurveConfig>(point: &Affine<P>) -> [Vec<String>; 3] {
+    let one = limbs(&P::BaseField::ONE);
+    match point.xy() {
+        Some((x, y)) => [limbs(&x), limbs(&y), one],
+        None => [
+            limbs(&P::BaseField::zero()),
+            one,
+            limbs(&P::BaseField::zero()),
+        ],
+    }
+}
+
+pub(crate) fn g1_to_snarkjs<P: SWCurveConfig>(point: &Affine<P>) -> [String; 3] {
+    projective(point).map(|mut c| c.remove(0))
+}
+
+pub(crate) fn g2_to_snarkjs<P: SWCurveConfig>(point: &Affine<P>) -> [[String; 2]; 3] {
+    projective(point).map(|c| [c[0].clone(), c[1].clone()])
+}
+
+/// Points are in Jacobian coordinates, as ffjavascript reads them, so `(x, y, z)`
+/// is `(x/z², y/z³)`. Any point with `z = 0` is the point at infinity.
+fn point_from_projective<P: SWCurveConfig>(
+    coords: [P::BaseField; 3],
+    path: &str,
+) -> Result<Affine<P>> {
+    let [x, y, z] = coords;
+    let z_inv = match z.inverse() {
+        Some(z_inv) => z_inv,
+        None => return Ok(Affine::identity()),
+    };
+    let z_inv2 = z_inv.square();
+    point_from_coords(x * z_inv2, y * z_inv2 * z_inv, path)
+}
+
+fn parse_coordinate<P: SWCurveConfig>(limbs: &[String], path: String) -> Result<P::BaseField> {
+    let mut elems = Vec::with_capacity(limbs.len());
+    for (i, limb) in limbs.iter().enumerate() {
+        let limb_path = match limbs.len() {
+            1 => path.clone(),
+            _ => format!("{}[{}]", path, i),
+        };
+        elems.push(parse_prime_field(limb, limb_path, "coordinate")?);
+    }
+    P::BaseField::from_base_prime_field_elems(elems)
+        .ok_or_else(|| Error::json(path, "wrong number of coordinate limbs"))
+}
+
+pub(crate) fn g1_from_snarkjs<P: SWCurveConfig>(
+    coords: &[String; 3],
+    path: &str,
+) -> Result<Affine<P>> {
+    let mut parsed = [P::BaseField::zero(); 3];
+    for (i, c) in coords.iter().enumerate() {
+        parsed[i] = parse_coordinate::<P>(std::slice::from_ref(c), format!("{}[{}]", path, i))?;
+    }
+    point_from_projective(parsed, path)
+}
+
+pub(crate) fn g2_from_snarkjs<P: SWCurveConfig>(
+    coords: &[[String; 2]; 3],
+    path: &str,
+) -> Result<Affine<P>> {
+    let mut parsed = [P::BaseField::zero(); 3];
+    for (i, c) in coords.iter().enumerate() {
+        parsed[i] = parse_coordinate::<P>(c, format!("{}[{}]", path, i))?;
+    }
+    point_from_projective(parsed, path)
+}
+
+/// The pairing of alpha and beta, `vk_alphabeta_12`. snarkjs ends the pairing
+/// with the same final exponentiation as arkworks, see `example/snarkjs`.
+fn alphabeta<E: CircomCurve>(vk: &VerifyingKey<E>) -> E::TargetField {
+    E::pairing(vk.alpha_g1, vk.beta_g2).0
+}
+
+fn fq12_to_snarkjs<F: Field>(f: &F) -> Fq12Json {
+    let c = limbs(f);
+    [0, 1].map(|i| [0, 1, 2].map(|j| [c[6 * i + 2 * j].clone(), c[6 * i + 2 * j + 1].clone()]))
+}
+
+fn fq12_from_snarkjs<F: Field>(json: &Fq12Json, path: &str) -> Result<F> {
+    let mut elems = Vec::with_capacity(12);
+    for (i, c) in json.iter().enumerate() {
+        for (j, c) in c.iter().enumerate() {
+            for (k, limb) in c.iter().enumerate() {
+                let limb_path = format!("{}[{}][{}][{}]", path, i, j, k);
+                elems.push(parse_prime_field(limb, limb_path, "coordinate")?);
+            }
+        }
+    }
+    F::from_base_prime_field_elems(elems)
+        .ok_or_else(|| Error::json(path, "wrong number of coordinate limbs"))
+}
+
+impl<E: CircomCurve> TryFrom<&VerifyingKey<E>> for SnarkjsVerifyingKeyJson {
+    type Error = Error;
+
+    fn try_from(vk: &VerifyingKey<E>) -> Result<Self> {
+        let n_public = n_public(vk)?;
+        Ok(SnarkjsVerifyingKeyJson {
+            protocol: "groth16".to_string(),
+            curve: E::NAME.to_string(),
+            n_public,
+            vk_alpha_1: g1_to_snarkjs(&vk.alpha_g1),
+            vk_beta_2: g2_to_snarkjs(&vk.beta_g2),
+            vk_gamma_2: g2_to_snarkjs(&vk.gamma_g2),
+            vk_delta_2: g2_to_snarkjs(&vk.delta_g2),
+            vk_alphabeta_12: Some(fq12_to_snarkjs(&alphabeta(vk))),
+            ic: vk.gamma_abc_g1.iter().map(g1_to_snarkjs).collect(),
+        })
+    }
+}
+
+impl<E: CircomCurve> TryFrom<SnarkjsVerifyingKeyJson> for VerifyingKey<E> {
+    type Error = Error;
+
+    fn try_from(json: SnarkjsVerifyingKeyJson) -> Result<Self> {
+        check_tag("$.protocol", "groth16", &json.protocol)?;
+        check_tag("$.curve", E::NAME, &json.curve)?;
+        if json.ic.len() != json.n_public + 1 {
+            return Err(Error::json(
+                "$.nPublic",
+                format!(
+                    "expected {} for {} IC points",
+                    json.ic.len().saturating_sub(1),
+                    json.ic.len()
+                ),
+            ));
+        }
+
+        let mut gamma_abc_g1 = Vec::with_capacity(json.ic.len());
+        for (i, coords) in json.ic.iter().enumerate() {
+            gamma_abc_g1.push(g1_from_snarkjs(coords, &format!("$.IC[{}]", i))?);
+        }
+
+        let vk = VerifyingKey {
+            alpha_g1: g1_from_snarkjs(&json.vk_alpha_1, "$.vk_alpha_1")?,
+            beta_g2: g2_from_snarkjs(&json.vk_beta_2, "$.vk_beta_2")?,
+            gamma_g2: g2_from_snarkjs(&json.vk_gamma_2, "$.vk_gamma_2")?,
+            delta_g2: g2_from_snarkjs(&json.vk_delta_2, "$.vk_delta_2")?,
+            gamma_abc_g1,
+        };
+
+        if let Some(expected) = &json.vk_alphabeta_12 {
+            let path = "$.vk_alphabeta_12";
+            let expected: E::TargetField = fq12_from_snarkjs(expected, path)?;
+            if expected != alphabeta(&vk) {
+                return Err(Error::json(
+                    path,
+                    "not the pairing of vk_alpha_1 and vk_beta_2",
+                ));
+            }
+        }
+        Ok(vk)
+    }
+}
+
+impl<E: CircomCurve> From<&Proof<E>> for SnarkjsProofJson {
+    fn from(proof: &Proof<E>) -> Self {
+        SnarkjsProofJson {
+            pi_a: g1_to_snarkjs(&proof.a),
+            pi_b: g2_to_snarkjs(&proof.b),
+            pi_c: g1_to_snarkjs(&proof.c),
+            protocol: "groth16".to_string(),
+            curve: E::NAME.to_string(),
+        }
+    }
+}
+
+impl<E: CircomCurve> TryFrom<SnarkjsProofJson> for Proof<E> {
+    type Error = Error;
+
+    fn try_from(json: SnarkjsProofJson) -> Result<Self> {
+        check_tag("$.protocol", "groth16", &json.protocol)?;
+        check_tag("$.curve", E::NAME, &json.curve)?;
+
+        Ok(Proof {
+            a: g1_from_snarkjs(&json.pi_a, "$.pi_a")?,
+            b: g2_from_snarkjs(&json.pi_b, "$.pi_b")?,
+            c: g1_from_snarkjs(&json.pi_c, "$.pi_c")?,
+        })
+    }
+}
+
+/// Write a proof as a snarkjs `proof.json`, the public inputs go in a
+/// separate `public.json`, see [`public_inputs_to_json`]
+pub fn proof_to_snarkjs_json<E: CircomCurve>(proof: &Proof<E>) -> Result<String> {
+    to_snarkjs_string(&SnarkjsProofJson::from(proof))
+}
+
+pub fn proof_from_snarkjs_json<E: CircomCurve>(json_str: &str) -> Result<Proof<E>> {
+    let json_proof: SnarkjsProofJson = serde_json::from_str(json_str)?;
+    Proof::try_from(json_proof)
+}
+
+/// Write a verifying key exactly as snarkjs writes `verification_key.json`,
+/// which its Solidity verifier template accepts unchanged. Errors if the key
+/// has no IC points.
+pub fn verifying_key_to_snarkjs_json<E: CircomCurve>(vk: &VerifyingKey<E>) -> Result<String> {
+    to_snarkjs_string(&SnarkjsVerifyingKeyJson::try_from(vk)?)
+}
+
+/// Read a snarkjs `verification_key.json`. `vk_alphabeta_12` may be left out,
+/// but if present it must match `vk_alpha_1` and `vk_beta_2`.
+pub fn verifying_key_from_snarkjs_json<E: CircomCurve>(json_str: &str) -> Result<VerifyingKey<E>> {
+    let json_vk: SnarkjsVerifyingKeyJson = serde_json::from_str(json_str)?;
+    VerifyingKey::try_from(json_vk)
+}
+
+/// Write public inputs as a snarkjs `public.json`, a list of decimal strings
+pub fn public_inputs_to_json<F: PrimeField>(public_inputs: &[F]) -> Result<String> {
+    let strings: Vec<String> = public_inputs.iter().map(|i| i.to_string()).collect();
+    to_snarkjs_string(&strings)
+}
+
+pub fn public_inputs_from_json<F: PrimeField>(json_str: &str) -> Result<Vec<F>> {
+    let strings: Vec<String> = serde_json::from_str(json_str)?;
+    strings
+        .iter()
+        .enumerate()
+        .map(|(i, s)| parse_prime_field(s, format!("$[{}]", i), "field element"))
+        .collect()
+}
+
+/// Whether a JSON proof is in the snarkjs format rather than this crate's own
+pub fn is_snarkjs_proof(json_str: &str) -> Result<bool> {
+    let value: serde_json::Value = serde_json::from_str(json_str)?;
+    Ok(value.get("pi_a").is_some())
+}
+
+/// Whether a JSON verifying key is in the snarkjs format, with projective
+/// points, rather than this crate's own
+pub(crate) fn is_snarkjs_verifying_key(value: &serde_json::Value) -> bool {
+    value
+        .get("vk_alpha_1")
+        .and_then(|alpha| alpha.as_array())
+        .is_some_and(|alpha| alpha.len() == 3)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::qap::CircomReduction;
+    use crate::utils::{setup_oneshot, verify_proof_json_with_public};
+    use crate::zkey::ZKey;
+    use crate::{proof_from_json, verifying_key_from_json, Prover};
+    use ark_bn254::{Bn254, Fr, G1Affine};
+    use ark_groth16::r1cs_to_qap::LibsnarkReduction;
+    use ark_std::io::Cursor;
+
+    // The proof from the jsonstructs tests, as snarkjs writes it
+    const PROOF: &str = r#"{
+ "pi_a": [
+  "19801287090726837578044200885001440254402844668381646296109323380504107979463",
+  "16223645295886758837836946460684489930824831996298592009362843244530537565095",
+  "1"
+ ],
+ "pi_b": [
+  [
+   "14419158160229828478156294188598564679024729352043826435394413630574520415635",
+   "13227541383047502364817281770506826380994950495371717515327355831245222863808"
+  ],
+  [
+   "10170314160637616072929693052506642860784907058519406560969697126545938282647",
+   "7701434206311278289343724947323209665008169752900822212424490783498470765028"
+  ],
+  [
+   "1",
+   "0"
+  ]
+ ],
+ "pi_c": [
+  "15931542535481606335382873551011159013606998048559820372751278530993816517682",
+  "9936785827820229135526754961312073112426012772106930225610536358663776965",
+  "1"
+ ],
+ "protocol": "groth16",
+ "curve": "bn128"
+}"#;
+
+    const OWN_PROOF: &str = r#"{
+	"protocol":"groth16",
+	"type":"proof",
+	"curve":"bn128",
+	"a": ["19801287090726837578044200885001440254402844668381646296109323380504107979463", "16223645295886758837836946460684489930824831996298592009362843244530537565095"],
+	"b": [["13227541383047502364817281770506826380994950495371717515327355831245222863808", "14419158160229828478156294188598564679024729352043826435394413630574520415635"], ["7701434206311278289343724947323209665008169752900822212424490783498470765028", "10170314160637616072929693052506642860784907058519406560969697126545938282647"]],
+	"c": ["15931542535481606335382873551011159013606998048559820372751278530993816517682", "9936785827820229135526754961312073112426012772106930225610536358663776965"],
+	"inputs": ["110", "11"]
+}"#;
+
+    #[test]
+    fn snarkjs_proof_round_trip() {
+        let proof = proof_from_snarkjs_json::<Bn254>(PROOF).unwrap();
+        let (expected, inputs) = proof_from_json::<Bn254>(OWN_PROOF).unwrap();
+        assert_eq!(proof, expected);
+        assert_eq!(proof_to_snarkjs_json(&proof).unwrap(), PROOF);
+
+        assert!(is_snarkjs_proof(PROOF).unwrap());
+        assert!(!is_snarkjs_proof(OWN_PROOF).unwrap());
+
+        let public = public_inputs_to_json(&inputs).unwrap();
+        assert_eq!(public, "[\n \"110\",\n \"11\"\n]");
+        assert_eq!(public_inputs_from_json::<Fr>(&public).unwrap(), inputs);
+    }
+
+    #[test]
+    fn projective_points() {
+        let g = G1Affine::generator();
+        let (x, y) = g.xy().unwrap();
+
+        // Any z, not only 1, as ffjavascript reads Jacobian coordinates
+        let z = <G1Affine as AffineRepr>::BaseField::from(5u64);
+        let coords = [x * z.square(), y * z.square() * z, z].map(|c| c.to_string());
+        assert_eq!(g1_from_snarkjs(&coords, "$").unwrap(), g);
+
+        let infinity = g1_to_snarkjs(&G1Affine::identity());
+        assert_eq!(infinity, ["0", "1", "0"]);
+        assert!(g1_from_snarkjs::<ark_bn254::g1::Config>(&infinity, "$")
+            .unwrap()
+            .is_zero());
+
+        let off_curve = [x.to_string(), x.to_string(), "1".to_string()];
+        match g1_from_snarkjs::<ark_bn254::g1::Config>(&off_curve, "$.pi_a") {
+            Err(Error::Json { path, .. }) => assert_eq!(path, "$.pi_a"),
+            _ => panic!("expected an off-curve point to be rejected"),
+        }
+
+        match public_inputs_from_json::<Fr>(r#"["1", "x"]"#) {
+            Err(Error::Json { path, .. }) => assert_eq!(path, "$[1]"),
+            _ => panic!("expected a bad public input to be rejected"),
+        }
+    }
+
+    #[test]
+    fn snarkjs_verifying_key_round_trip() {
+        let r1cs_data = include_bytes!("../example/multiply.r1cs");
+        let vk = setup_oneshot::<Bn254, LibsnarkReduction>(r1cs_data)
+            .unwrap()
+            .vk;
+
+        let json = verifying_key_to_snarkjs_json(&vk).unwrap();
+        assert!(json.starts_with(
+            "{\n \"protocol\": \"groth16\",\n \"curve\": \"bn128\",\n \"nPublic\": 2,"
+        ));
+        assert_eq!(verifying_key_from_snarkjs_json::<Bn254>(&json).unwrap(), vk);
+        assert_eq!(verifying_key_from_json::<Bn254>(&json).unwrap(), vk);
+
+        let mut value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        value["vk_alphabeta_12"][1][2][0] = "1".into();
+        match verifying_key_from_json::<Bn254>(&value.to_string()) {
+            Err(Error::Json { path, .. }) => assert_eq!(path, "$.vk_alphabeta_12"),
+            _ => panic!("expected a wrong vk_alphabeta_12 to be rejected"),
+        }
+
+        value.as_object_mut().unwrap().remove("vk_alphabeta_12");
+        assert_eq!(
+            verifying_key_from_json::<Bn254>(&value.to_string()).unwrap(),
+            vk
+        );
+
+        value["nPublic"] = 3.into();
+        match verifying_key_from_json::<Bn254>(&value.to_string()) {
+            Err(Error::Json { path, .. }) => assert_eq!(path, "$.nPublic"),
+            _ => panic!("expected a wrong nPublic to be rejected"),
+        }
+
+        let mut empty = vk.clone();
+        empty.gamma_abc_g1.clear();
+        assert!(matches!(
+            verifying_key_to_snarkjs_json(&empty),
+            Err(Error::Mismatch(_))
+        ));
+    }
+
+    #[test]
+    fn snarkjs_verification_key_file() {
+        // Exported by snarkjs from `multiplier.zkey`
+        let json = include_str!("../example/snarkjs/verification_key.json");
+        let vk = verifying_key_from_json::<Bn254>(json).unwrap();
+        assert_eq!(verifying_key_from_snarkjs_json::<Bn254>(json).unwrap(), vk);
+        assert_eq!(verifying_key_to_snarkjs_json(&vk).unwrap(), json);
+
+        let data = include_bytes!("../example/snarkjs/multiplier.zkey");
+        let zkey = ZKey::<Bn254>::new(Cursor::new(&data[..])).unwrap();
+        assert!(zkey.proving_key.vk == vk);
+
+        // There's no proof from snarkjs for this key, prove a = 3, b = 11 here
+        let r1cs_data = include_bytes!("../example/snarkjs/multiplier.r1cs");
+        let prover =
+            Prover::<Bn254, CircomReduction>::from_zkey_without_graph(data, r1cs_data).unwrap();
+        let (proof, public_inputs) = prover
+            .prove_witness([1, 33, 3, 11].map(Fr::from).to_vec())
+            .unwrap();
+        let proof_json = proof_to_snarkjs_json(&proof).unwrap();
+        let public_json = public_inputs_to_json(&public_inputs).unwrap();
+        assert_eq!(public_json, "[\n \"33\"\n]");
+        assert!(
+            verify_proof_json_with_public::<Bn254>(json, &proof_json, Some(&public_json)).unwrap()
+        );
+    }
+}
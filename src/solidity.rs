@@ -0,0 +1,399 @@
+//! A Solidity contract verifying Groth16 proofs for one BN254 verifying key,
+//! using the EIP-196 and EIP-197 precompiles for point addition, scalar
+//! multiplication and the pairing check, and the calldata to call it with.
+
+use ark_bn254::{Bn254, Fq, Fr, G1Affine, G2Affine};
+use ark_ff::{BigInteger, PrimeField};
+use ark_groth16::{Proof, VerifyingKey};
+use std::fmt::Write;
+
+use crate::error::Result;
+use crate::utils::n_public;
+
+/// Name of the contract written by [`solidity_verifier`]
+pub const CONTRACT_NAME: &str = "Groth16Verifier";
+
+/// First four bytes of the keccak256 hash of
+/// `verifyProof(uint256[2],uint256[2][2],uint256[2],uint256[])`
+pub const VERIFY_PROOF_SELECTOR: [u8; 4] = [0xc3, 0x2e, 0x37, 0x0e];
+
+/// Coordinates as the precompiles take them, G2 coordinates as `c1, c0`
+fn g1_words(point: &G1Affine) -> [Fq; 2] {
+    [point.x, point.y]
+}
+
+fn g2_words(point: &G2Affine) -> [Fq; 4] {
+    [point.x.c1, point.x.c0, point.y.c1, point.y.c0]
+}
+
+/// A 32 byte big-endian EVM word
+fn evm_word<F: PrimeField>(f: &F) -> Vec<u8> {
+    let bytes = f.into_bigint().to_bytes_be();
+    let mut word = vec![0u8; 32 - bytes.len()];
+    word.extend(bytes);
+    word
+}
+
+fn evm_word_u64(n: u64) -> Vec<u8> {
+    evm_word(&Fr::from(n))
+}
+
+fn g1_constants(out: &mut String, name: &str, point: &G1Affine) {
+    let [x, y] = g1_words(point);
+    writeln!(out, "    uint256 constant {}_X = {};", name, x).unwrap();
+    writeln!(out, "    uint256 constant {}_Y = {};", name, y).unwrap();
+}
+
+fn g2_constants(out: &mut String, name: &str, point: &G2Affine) {
+    let words = g2_words(point);
+    for (suffix, word) in ["X1", "X0", "Y1", "Y0"].iter().zip(words) {
+        writeln!(out, "    uint256 constant {}_{} = {};", name, suffix, word).unwrap();
+    }
+}
+
+/// Render a Solidity verifier for `vk` with the constants embedded. Its
+/// `verifyProof(uint256[2], uint256[2][2], uint256[2], uint256[])` takes the
+/// proof points in precompile order, which is how [`crate::proof_to_json`]
+/// writes them, and returns false for any invalid proof or public input.
+/// Errors if the key has no IC points, not even the constant term.
+pub fn solidity_verifier(vk: &VerifyingKey<Bn254>) -> Result<String> {
+    let n_public = n_public(vk)?;
+    let mut out = String::new();
+
+    out.push_str("// SPDX-License-Identifier: MIT\n");
+    out.push_str("// Generated by ark-circom-witnesscalc\n");
+    out.push_str("pragma solidity ^0.8.0;\n\n");
+    writeln!(out, "contract {} {{", CONTRACT_NAME).unwrap();
+    writeln!(out, "    // Scalar field modulus").unwrap();
+    writeln!(out, "    uint256 constant R = {};", Fr::MODULUS).unwrap();
+    writeln!(out, "    // Base field modulus").unwrap();
+    writeln!(out, "    uint256 constant Q = {};", ark_bn254::Fq::MODULUS).unwrap();
+    writeln!(out, "    uint256 constant N_PUBLIC = {};\n", n_public).unwrap();
+
+    writeln!(
+        out,
+        "    // G2 coordinates are c1 then c0, as the pairing precompile expects"
+    )
+    .unwrap();
+    g1_constants(&mut out, "ALPHA", &vk.alpha_g1);
+    g2_constants(&mut out, "BETA", &vk.beta_g2);
+    g2_constants(&mut out, "GAMMA", &vk.gamma_g2);
+    g2_constants(&mut out, "DELTA", &vk.delta_g2);
+    for (i, point) in vk.gamma_abc_g1.iter().enumerate() {
+        g1_constants(&mut out, &format!("IC{}", i), point);
+    }
+
+    out.push_str(
+        r#"
+    /// Add `s` times `(x, y)` to `acc`, returns false if the precompiles fail
+    function mulAdd(uint256[2] memory acc, uint256 x, uint256 y, uint256 s) internal view returns (bool ok) {
+        uint256[4] memory p;
+        p[0] = x;
+        p[1] = y;
+        p[2] = s;
+        assembly {
+            ok := staticcall(gas(), 0x07, p, 0x60, add(p, 0x40), 0x40)
+            if ok {
+                mstore(p, mload(acc))
+                mstore(add(p, 0x20), mload(add(acc, 0x20)))
+                ok := staticcall(gas(), 0x06, p, 0x80, acc, 0x40)
+            }
+        }
+    }
+
+    function verifyProof(
+        uint256[2] calldata a,
+        uint256[2][2] calldata b,
+        uint256[2] calldata c,
+        uint256[] calldata input
+    ) public view returns (bool) {
+        if (input.length != N_PUBLIC) {
+            return false;
+        }
+        for (uint256 i = 0; i < N_PUBLIC; i++) {
+            if (input[i] >= R) {
+                return false;
+            }
+        }
+
+        uint256[2] memory vkX;
+        vkX[0] = IC0_X;
+        vkX[1] = IC0_Y;
+"#,
+    );
+    for i in 1..=n_public {
+        writeln!(
+            out,
+            "        if (!mulAdd(vkX, IC{i}_X, IC{i}_Y, input[{}])) {{\n            return false;\n        }}",
+            i - 1
+        )
+        .unwrap();
+    }
+
+    out.push_str(
+        r#"
+        // e(-A, B) e(alpha, beta) e(vkX, gamma) e(C, delta) == 1
+        uint256[24] memory p;
+        p[0] = a[0];
+        p[1] = (Q - (a[1] % Q)) % Q;
+        p[2] = b[0][0];
+        p[3] = b[0][1];
+        p[4] = b[1][0];
+        p[5] = b[1][1];
+        p[6] = ALPHA_X;
+        p[7] = ALPHA_Y;
+        p[8] = BETA_X1;
+        p[9] = BETA_X0;
+        p[10] = BETA_Y1;
+        p[11] = BETA_Y0;
+        p[12] = vkX[0];
+        p[13] = vkX[1];
+        p[14] = GAMMA_X1;
+        p[15] = GAMMA_X0;
+        p[16] = GAMMA_Y1;
+        p[17] = GAMMA_Y0;
+        p[18] = c[0];
+        p[19] = c[1];
+        p[20] = DELTA_X1;
+        p[21] = DELTA_X0;
+        p[22] = DELTA_Y1;
+        p[23] = DELTA_Y0;
+
+        uint256[1] memory out;
+        bool ok;
+        assembly {
+            ok := staticcall(gas(), 0x08, p, 0x300, out, 0x20)
+        }
+        return ok && out[0] == 1;
+    }
+}
+"#,
+    );
+    Ok(out)
+}
+
+/// A proof as the EIP-197 pairing precompile reads points: A, B, then C, with
+/// each coordinate a 32 byte big-endian word and G2 coordinates as `c1, c0`
+pub fn proof_to_eip197(proof: &Proof<Bn254>) -> [u8; 256] {
+    let words = g1_words(&proof.a)
+        .iter()
+        .chain(&g2_words(&proof.b))
+        .chain(&g1_words(&proof.c))
+        .flat_map(evm_word)
+        .collect::<Vec<u8>>();
+    // Eight 32 byte words
+    words.try_into().unwrap()
+}
+
+/// ABI-encoded calldata calling `verifyProof` on the contract from
+/// [`solidity_verifier`], including the function selector
+pub fn proof_to_calldata(proof: &Proof<Bn254>, public_inputs: &[Fr]) -> Vec<u8> {
+    let mut out = VERIFY_PROOF_SELECTOR.to_vec();
+    out.extend(proof_to_eip197(proof));
+    // The public inputs are a dynamic array, so the head holds its offset,
+    // which is after the eight words of the proof and the offset itself
+    out.extend(evm_word_u64(9 * 32));
+    out.extend(evm_word_u64(public_inputs.len() as u64));
+    for input in public_inputs {
+        out.extend(evm_word(input));
+    }
+    out
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::error::Error;
+    use crate::utils::setup_oneshot;
+    use crate::{CircomCircuit, R1CSFile};
+    use ark_crypto_primitives::snark::SNARK;
+    use ark_ec::AffineRepr;
+    use ark_ff::{One, Zero};
+    use ark_groth16::r1cs_to_qap::LibsnarkReduction;
+    use ark_groth16::{Groth16, Proof};
+    use ark_std::io::Cursor;
+    use ark_std::rand::thread_rng;
+
+    /// A 32 byte big-endian EVM word
+    fn word(s: &str) -> [u8; 32] {
+        let n: num_bigint::BigUint = s.parse().unwrap();
+        let bytes = n.to_bytes_be();
+        let mut w = [0u8; 32];
+        w[32 - bytes.len()..].copy_from_slice(&bytes);
+        w
+    }
+
+    /// A proof of `10 * 11 = 110` with the example circuit, and its key
+    pub(crate) fn example_proof() -> (VerifyingKey<Bn254>, Proof<Bn254>, Vec<Fr>) {
+        let r1cs_data = include_bytes!("../example/multiply.r1cs");
+        let pk = setup_oneshot::<Bn254, LibsnarkReduction>(r1cs_data).unwrap();
+        let circuit = CircomCircuit::<Fr> {
+            r1cs: R1CSFile::new(Cursor::new(r1cs_data))
+                .unwrap()
+                .try_into()
+                .unwrap(),
+            witness: Some([1, 110, 11, 10].map(Fr::from).to_vec()),
+        };
+        let inputs = circuit.get_public_inputs().unwrap();
+        let proof =
+            Groth16::<Bn254, LibsnarkReduction>::prove(&pk, circuit, &mut thread_rng()).unwrap();
+        (pk.vk, proof, inputs)
+    }
+
+    /// Compile a contract with `solc` from the `PATH`, returning its creation bytecode
+    pub(crate) fn solc(source: &str) -> Vec<u8> {
+        use std::io::Write;
+        use std::process::{Command, Stdio};
+
+        let mut child = Command::new("solc")
+            .args(["--optimize", "--combined-json", "bin", "-"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("solc is on the PATH");
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(source.as_bytes())
+            .unwrap();
+        let output = child.wait_with_output().unwrap();
+        assert!(output.status.success(), "solc failed");
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        let bin = json["contracts"][format!("<stdin>:{}", CONTRACT_NAME)]["bin"]
+            .as_str()
+            .expect("solc output has the contract");
+        hex::decode(bin).unwrap()
+    }
+
+    /// A contract deployed to an in-memory EVM, with the mainnet precompiles
+    pub(crate) struct EvmContract {
+        evm: revm::MainnetEvm<revm::handler::MainnetContext<revm::database::InMemoryDB>>,
+        address: revm::primitives::Address,
+        nonce: u64,
+    }
+
+    impl EvmContract {
+        pub(crate) fn deploy(bytecode: Vec<u8>) -> Self {
+            use revm::context::result::{ExecutionResult, Output};
+            use revm::primitives::TxKind;
+            use revm::{Context, ExecuteCommitEvm, MainBuilder, MainContext};
+
+            let mut evm = Context::mainnet()
+                .with_db(revm::database::InMemoryDB::default())
+                .build_mainnet();
+            let tx = revm::context::TxEnv::builder()
+                .kind(TxKind::Create)
+                .data(bytecode.into())
+                .gas_limit(10_000_000)
+                .build()
+                .unwrap();
+            match evm.transact_commit(tx).unwrap() {
+                ExecutionResult::Success {
+                    output: Output::Create(_, Some(address)),
+                    ..
+                } => EvmContract {
+                    evm,
+                    address,
+                    nonce: 1,
+                },
+                result => panic!("deployment failed: {:?}", result),
+            }
+        }
+
+        /// Call the contract, `None` if it reverts
+        pub(crate) fn call(&mut self, calldata: Vec<u8>) -> Option<Vec<u8>> {
+            use revm::context::result::ExecutionResult;
+            use revm::primitives::TxKind;
+            use revm::ExecuteCommitEvm;
+
+            let tx = revm::context::TxEnv::builder()
+                .kind(TxKind::Call(self.address))
+                .data(calldata.into())
+                .gas_limit(10_000_000)
+                .nonce(self.nonce)
+                .build()
+                .unwrap();
+            self.nonce += 1;
+            match self.evm.transact_commit(tx).unwrap() {
+                ExecutionResult::Success { output, .. } => Some(output.into_data().to_vec()),
+                _ => None,
+            }
+        }
+
+        /// Call `verifyProof`, which must return a bool rather than revert
+        pub(crate) fn verify(&mut self, calldata: Vec<u8>) -> bool {
+            let output = self.call(calldata).expect("verifyProof doesn't revert");
+            assert_eq!(output.len(), 32);
+            assert!(output[..31].iter().all(|b| *b == 0));
+            output[31] == 1
+        }
+    }
+
+    #[test]
+    fn verifier_embeds_the_key() {
+        let (vk, _, _) = example_proof();
+        let source = solidity_verifier(&vk).unwrap();
+        assert!(source.contains("contract Groth16Verifier {"));
+        assert!(source.contains("uint256 constant N_PUBLIC = 2;"));
+        for i in 0..=2 {
+            assert!(source.contains(&format!("uint256 constant IC{}_X = ", i)));
+        }
+
+        let mut empty = vk.clone();
+        empty.gamma_abc_g1.clear();
+        assert!(matches!(solidity_verifier(&empty), Err(Error::Mismatch(_))));
+    }
+
+    #[test]
+    fn calldata_encodes_verify_proof() {
+        let (_, proof, inputs) = example_proof();
+        let calldata = proof_to_calldata(&proof, &inputs);
+        assert_eq!(calldata.len(), 4 + 32 * (8 + 2 + inputs.len()));
+        assert_eq!(calldata[..4], VERIFY_PROOF_SELECTOR);
+        assert_eq!(calldata[4..260], proof_to_eip197(&proof));
+        // The offset of the public inputs, their count, then the inputs
+        assert_eq!(calldata[260..292], evm_word_u64(9 * 32));
+        assert_eq!(calldata[292..324], evm_word_u64(2));
+        assert_eq!(calldata[324..356], word("110"));
+    }
+
+    #[test]
+    #[ignore = "needs solc on the PATH, see `make test-evm`"]
+    fn evm_verifier_accepts_only_valid_proofs() {
+        let (vk, proof, inputs) = example_proof();
+        let mut verifier = EvmContract::deploy(solc(&solidity_verifier(&vk).unwrap()));
+
+        assert!(verifier.verify(proof_to_calldata(&proof, &inputs)));
+
+        // A tampered proof, and A and C swapped
+        let mut tampered = proof.clone();
+        tampered.a = (tampered.a + G1Affine::generator()).into();
+        assert!(!verifier.verify(proof_to_calldata(&tampered, &inputs)));
+        let mut swapped = proof_to_calldata(&proof, &inputs);
+        let (a, rest) = swapped[4..260].split_at_mut(64);
+        a.swap_with_slice(&mut rest[128..]);
+        assert!(!verifier.verify(swapped));
+        // The wrong public input, and its non-canonical form x + r
+        let wrong = vec![inputs[0], inputs[1] + Fr::one()];
+        assert!(!verifier.verify(proof_to_calldata(&proof, &wrong)));
+        let mut calldata = proof_to_calldata(&proof, &inputs);
+        let r = num_bigint::BigUint::from(Fr::MODULUS);
+        let last = calldata.len() - 32;
+        calldata[last..].copy_from_slice(&word(&(r + 11u32).to_string()));
+        assert!(!verifier.verify(calldata));
+
+        // The wrong number of public inputs
+        assert!(!verifier.verify(proof_to_calldata(&proof, &inputs[..1])));
+        let extra = vec![inputs[0], inputs[1], Fr::zero()];
+        assert!(!verifier.verify(proof_to_calldata(&proof, &extra)));
+
+        // G2 coordinates in arkworks order, c0 first, are rejected
+        let mut calldata = proof_to_calldata(&proof, &inputs);
+        for word in [2, 4] {
+            let (x1, x0) = calldata[4 + 32 * word..4 + 32 * (word + 2)].split_at_mut(32);
+            x1.swap_with_slice(x0);
+        }
+        assert!(!verifier.verify(calldata));
+    }
+}
@@ -0,0 +1,248 @@
+//! Signal names from circom's `.sym` file
+//!
+//! Each line is `label,wire,component,name`, e.g. `3,2,0,main.a`. Every signal
+//! has a label, and the wire it was assigned to, or -1 if the optimizer removed
+//! it. Several labels can share a wire when signals are constrained equal.
+
+use std::collections::HashMap;
+
+use indexmap::IndexMap;
+
+use crate::error::{Error, Result};
+
+/// The public signals of a proof by name, in the order of the public inputs:
+/// the outputs of the main component, then its public inputs
+pub type PublicSignals<F> = IndexMap<String, F>;
+
+/// One line of the `.sym` file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Symbol {
+    pub label: usize,
+    /// `None` for signals removed by the optimizer
+    pub wire: Option<usize>,
+    pub component: usize,
+    /// The fully qualified name, e.g. `main.hasher.out[3]`
+    pub name: String,
+}
+
+/// Lookup from wire to signal name and back, to label witnesses, public
+/// inputs and constraint diagnostics
+#[derive(Clone, Debug, Default)]
+pub struct SymbolTable {
+    symbols: Vec<Symbol>,
+    by_name: HashMap<String, usize>,
+    by_wire: HashMap<usize, usize>,
+}
+
+impl SymbolTable {
+    /// Parse the contents of a `.sym` file
+    pub fn from_sym(data: &str) -> Result<SymbolTable> {
+        let mut table = SymbolTable::default();
+        for (i, line) in data.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let symbol = parse_line(line).map_err(|message| Error::Sym {
+                line: i + 1,
+                message,
+            })?;
+            let index = table.symbols.len();
+            if table.by_name.insert(symbol.name.clone(), index).is_some() {
+                return Err(Error::Sym {
+                    line: i + 1,
+                    message: format!("Duplicate signal name '{}'", symbol.name),
+                });
+            }
+            // The first name for a wire is the one closest to main
+            if let Some(wire) = symbol.wire {
+                table.by_wire.entry(wire).or_insert(index);
+            }
+            table.symbols.push(symbol);
+        }
+        Ok(table)
+    }
+
+    /// Every symbol, in the order of the file
+    pub fn symbols(&self) -> &[Symbol] {
+        &self.symbols
+    }
+
+    /// The name of a wire, the first signal assigned to it
+    pub fn name(&self, wire: usize) -> Option<&str> {
+        self.by_wire
+            .get(&wire)
+            .map(|i| self.symbols[*i].name.as_str())
+    }
+
+    /// The wire of a signal, `None` if unknown or removed by the optimizer
+    pub fn wire(&self, name: &str) -> Option<usize> {
+        self.symbol(name)?.wire
+    }
+
+    pub fn symbol(&self, name: &str) -> Option<&Symbol> {
+        self.by_name.get(name).map(|i| &self.symbols[*i])
+    }
+
+    /// Every named wire of the witness, in wire order
+    pub fn label_witness<'a, F>(&'a self, witness: &'a [F]) -> Vec<(&'a str, &'a F)> {
+        witness
+            .iter()
+            .enumerate()
+            .filter_map(|(wire, value)| Some((self.name(wire)?, value)))
+            .collect()
+    }
+
+    /// Name each public input, which are the wires after the constant 1
+    pub fn label_public_inputs<'a, F>(
+        &'a self,
+        public_inputs: &'a [F],
+    ) -> Result<Vec<(&'a str, &'a F)>> {
+        public_inputs
+            .iter()
+            .enumerate()
+            .map(|(i, value)| match self.name(i + 1) {
+                Some(name) => Ok((name, value)),
+                None => Err(Error::Mismatch(format!(
+                    "public input {} has no name in the symbol table",
+                    i
+                ))),
+            })
+            .collect()
+    }
+
+    /// The public inputs keyed by signal name without the `main.` prefix,
+    /// e.g. `nullifierHash` or `out[1]`
+    pub fn public_signals<F: Copy>(&self, public_inputs: &[F]) -> Result<PublicSignals<F>> {
+        Ok(self
+            .label_public_inputs(public_inputs)?
+            .into_iter()
+            .map(|(name, value)| (signal_name(name).to_string(), *value))
+            .collect())
+    }
+
+    /// Check the symbols are for the circuit with these inputs, from the
+    /// witness graph, so a `.sym` from another circuit isn't used to name signals
+    pub(crate) fn check_inputs<'a>(
+        &self,
+        inputs: impl IntoIterator<Item = &'a String>,
+    ) -> Result<()> {
+        for input in inputs {
+            // Array inputs only have symbols for their elements, e.g. `main.x[0][0]`
+            let name = format!("main.{}", input);
+            let prefix = format!("{}[", name);
+            if !self
+                .symbols
+                .iter()
+                .any(|s| s.name == name || s.name.starts_with(&prefix))
+            {
+                return Err(Error::Mismatch(format!(
+                    "the symbol table has no signal for the input '{}'",
+                    input
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A signal name relative to the main component
+fn signal_name(name: &str) -> &str {
+    name.strip_prefix("main.").unwrap_or(name)
+}
+
+fn parse_line(line: &str) -> std::result::Result<Symbol, String> {
+    let fields: Vec<&str> = line.splitn(4, ',').collect();
+    let [label, wire, component, name] = fields[..] else {
+        return Err(format!("Expected 4 fields, found {}", fields.len()));
+    };
+    let number = |field: &str, what: &str| {
+        field
+            .parse::<usize>()
+            .map_err(|_| format!("Invalid {} '{}'", what, field))
+    };
+    Ok(Symbol {
+        label: number(label, "label")?,
+        wire: match wire {
+            "-1" => None,
+            wire => Some(number(wire, "wire")?),
+        },
+        component: number(component, "component")?,
+        name: name.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuit::CircomCircuit;
+    use crate::r1cs_reader::R1CSFile;
+    use ark_bn254::Fr;
+    use std::io::Cursor;
+
+    // What circom writes for example/multiply.circom
+    const MULTIPLY_SYM: &str = "1,1,0,main.c\n2,2,0,main.a\n3,3,0,main.b\n";
+
+    #[test]
+    fn lookup_both_ways() {
+        let table =
+            SymbolTable::from_sym("1,1,0,main.out\n2,-1,0,main.tmp\n3,1,1,main.sub.out\n").unwrap();
+        assert_eq!(table.name(1), Some("main.out"));
+        assert_eq!(table.wire("main.sub.out"), Some(1));
+        assert_eq!(table.wire("main.tmp"), None);
+        assert_eq!(table.symbol("main.tmp").unwrap().label, 2);
+        assert_eq!(table.wire("main.missing"), None);
+        assert_eq!(table.name(0), None);
+
+        let err = SymbolTable::from_sym("1,1,0,main.a\n2,x,0,main.b\n").unwrap_err();
+        assert!(matches!(err, Error::Sym { line: 2, .. }), "{}", err);
+        assert!(SymbolTable::from_sym("1,1,main.a").is_err());
+        assert!(SymbolTable::from_sym("1,1,0,main.a\n2,2,0,main.a").is_err());
+    }
+
+    #[test]
+    fn labelled_diagnostics() {
+        let table = SymbolTable::from_sym(MULTIPLY_SYM).unwrap();
+        let witness = [1u64, 111, 11, 10].map(Fr::from);
+        let circuit = CircomCircuit {
+            r1cs: R1CSFile::new(Cursor::new(&include_bytes!("../example/multiply.r1cs")[..]))
+                .unwrap()
+                .try_into()
+                .unwrap(),
+            witness: Some(witness.to_vec()),
+        };
+
+        let public_inputs = circuit.get_public_inputs().unwrap();
+        let labelled = table.label_public_inputs(&public_inputs).unwrap();
+        assert_eq!(
+            labelled,
+            vec![("main.c", &Fr::from(111)), ("main.a", &Fr::from(11))]
+        );
+        assert_eq!(table.label_witness(&witness).len(), 3);
+        let signals = table.public_signals(&public_inputs).unwrap();
+        assert_eq!(signals.keys().collect::<Vec<_>>(), ["c", "a"]);
+        assert_eq!(signals["c"], Fr::from(111));
+
+        assert!(table
+            .check_inputs(&["a".to_string(), "b".to_string()])
+            .is_ok());
+        assert!(table.check_inputs(&["x".to_string()]).is_err());
+
+        // `signal input x[2][3]` and `signal input tx.to` of a bus
+        let arrays = SymbolTable::from_sym(
+            "1,1,0,main.out\n2,2,0,main.x[0][0]\n3,3,0,main.x[0][1]\n4,4,0,main.tx.to\n",
+        )
+        .unwrap();
+        assert!(arrays
+            .check_inputs(&["x".to_string(), "tx.to".to_string()])
+            .is_ok());
+        assert!(arrays.check_inputs(&["x[0]".to_string()]).is_ok());
+        assert!(arrays.check_inputs(&["tx".to_string()]).is_err());
+        assert!(arrays.check_inputs(&["ou".to_string()]).is_err());
+
+        let unsatisfied = circuit.check_witness().unwrap();
+        let text = unsatisfied[0].display_with(&table).to_string();
+        assert!(text.contains("wire 1 (main.c) = 111"), "{}", text);
+        assert!(text.contains("wire 3 (main.b) = 10"), "{}", text);
+    }
+}
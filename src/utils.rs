@@ -2,131 +2,275 @@ use std::collections::HashMap;
 use std::io::Cursor;
 use std::vec;
 
-use anyhow::anyhow;
-use anyhow::Result;
-use ark_groth16::prepare_verifying_key;
-use ark_groth16::VerifyingKey;
+use ark_ec::pairing::Pairing;
+use ark_groth16::r1cs_to_qap::R1CSToQAP;
+use ark_groth16::{prepare_verifying_key, PreparedVerifyingKey};
+use ark_groth16::{ProvingKey, VerifyingKey};
+use ark_std::rand::thread_rng;
 
 use crate::circuit::CircomCircuit;
+use crate::curve::CircomCurve;
+use crate::error::{Error, Result};
+use crate::graph::Graph;
+use crate::inputs::Inputs;
 use crate::proof_from_json;
-use crate::r1cs_reader::R1CSFile;
+use crate::prover::{ProofWithInputs, Prover};
+use crate::r1cs_reader::{R1CSFile, R1CS};
+use crate::snarkjs::{is_snarkjs_proof, proof_from_snarkjs_json, public_inputs_from_json};
 use crate::verifying_key_from_json;
 
 use circom_witnesscalc::{
-    field::{Field, FieldOperations, FieldOps, U254},
-    graph::{evaluate, Nodes, NodesInterface, NodesStorage, VecNodes},
-    storage::proto_deserializer::deserialize_witnesscalc_graph_from_bytes,
+    field::{Field, FieldOperations, FieldOps},
+    graph::{evaluate, Nodes, NodesStorage},
     InputSignalsInfo,
 };
 
-use ark_bn254::{Bn254, Fr as Bn254Fr};
-use ark_crypto_primitives::snark::SNARK;
 use ark_ff::PrimeField;
-use ark_groth16::{r1cs_to_qap::LibsnarkReduction, Groth16, Proof, ProvingKey};
+use ark_groth16::{r1cs_to_qap::LibsnarkReduction, Groth16, Proof};
 use ark_serialize::CanonicalDeserialize;
-use ark_std::rand::thread_rng;
+use num_bigint::{BigInt, BigUint};
 
 pub fn calc_len(vs: &Vec<serde_json::Value>) -> usize {
-    let mut len = vs.len();
-
-    for v in vs {
-        if let serde_json::Value::Array(arr) = v {
-            len += calc_len(arr) - 1;
-        }
-    }
-
-    len
+    vs.iter()
+        .map(|v| match v {
+            serde_json::Value::Array(arr) => calc_len(arr),
+            _ => 1,
+        })
+        .sum()
 }
 
 pub fn flatten_array2<T: FieldOps>(
     key: &str,
     vs: &Vec<serde_json::Value>,
     ff: &Field<T>,
-) -> Result<Vec<T>, Box<dyn std::error::Error>> {
+) -> Result<Vec<T>> {
     let mut vals: Vec<T> = Vec::with_capacity(calc_len(vs));
+    flatten_values(key, vs, &|v| parse_input_value(v, ff), &mut vals)?;
+    Ok(vals)
+}
 
-    for v in vs {
+/// Append every value of a possibly nested array, naming the element in errors
+fn flatten_values<V>(
+    path: &str,
+    vs: &[serde_json::Value],
+    parse: &dyn Fn(&serde_json::Value) -> std::result::Result<V, String>,
+    vals: &mut Vec<V>,
+) -> Result<()> {
+    for (i, v) in vs.iter().enumerate() {
         match v {
-            serde_json::Value::String(s) => {
-                let i = ff.parse_str(s)?;
-                vals.push(i);
-            }
-            serde_json::Value::Number(n) => {
-                if !n.is_u64() {
-                    return Err(anyhow!("signal value is not a positive integer").into());
-                }
-                let n = n.as_u64().unwrap().to_string();
-                let i = ff.parse_str(&n)?;
-                vals.push(i);
-            }
             serde_json::Value::Array(arr) => {
-                vals.extend_from_slice(flatten_array2(key, arr, ff)?.as_slice());
+                flatten_values(&format!("{}[{}]", path, i), arr, parse, vals)?
             }
-            _ => {
-                return Err(anyhow!("inputs must be a string: {}", key).into());
+            v => vals.push(parse(v).map_err(|e| Error::input(format!("{}[{}]", path, i), e))?),
+        }
+    }
+    Ok(())
+}
+
+/// Whether an input is an object, or an array with an object at any depth
+fn contains_object(v: &serde_json::Value) -> bool {
+    match v {
+        serde_json::Value::Object(_) => true,
+        serde_json::Value::Array(items) => items.iter().any(contains_object),
+        _ => false,
+    }
+}
+
+/// Flatten one input to signals named by their full path: objects to dotted
+/// names like `tx.from`, and arrays of objects to `tx[0].from` or, nested,
+/// `tx[0][1].from`. Arrays of values are one signal.
+fn flatten_input<V>(
+    path: String,
+    v: &serde_json::Value,
+    parse: &dyn Fn(&serde_json::Value) -> std::result::Result<V, String>,
+    signals: &mut Vec<(String, Vec<V>)>,
+) -> Result<()> {
+    match v {
+        serde_json::Value::Object(map) => {
+            for (k, v) in map {
+                flatten_input(format!("{}.{}", path, k), v, parse, signals)?;
             }
+        }
+        serde_json::Value::Array(items) if contains_object(v) => {
+            for (i, item) in items.iter().enumerate() {
+                let path = format!("{}[{}]", path, i);
+                if !contains_object(item) {
+                    return Err(Error::input(
+                        path,
+                        "arrays must be all objects or all values",
+                    ));
+                }
+                flatten_input(path, item, parse, signals)?;
+            }
+        }
+        serde_json::Value::Array(items) => {
+            let mut vals = Vec::with_capacity(calc_len(items));
+            flatten_values(&path, items, parse, &mut vals)?;
+            signals.push((path, vals));
+        }
+        v => {
+            let value = parse(v).map_err(|e| Error::input(&path, e))?;
+            signals.push((path, vec![value]));
+        }
+    }
+    Ok(())
+}
+
+/// Parse one input value as circom's witness generators do: a decimal or
+/// `0x` hex integer as a string or number, of any size and possibly negative,
+/// reduced mod p, or a boolean as 1 or 0
+fn parse_input_value<T: FieldOps>(
+    v: &serde_json::Value,
+    ff: &Field<T>,
+) -> std::result::Result<T, String> {
+    let prime = BigUint::from_bytes_le(ff.prime.as_le_slice());
+    let value = canonical_input(v, &prime)?;
+    ff.parse_str(&value.to_string()).map_err(|e| e.to_string())
+}
+
+/// The value of one input, reduced to `[0, prime)`
+fn canonical_input(v: &serde_json::Value, prime: &BigUint) -> std::result::Result<BigUint, String> {
+    let value =
+        match v {
+            serde_json::Value::Bool(b) => BigInt::from(*b as u8),
+            // With arbitrary precision numbers keep their digits, so big integers
+            // aren't rounded through f64
+            serde_json::Value::Number(n) => parse_input_integer(&n.to_string())?,
+            serde_json::Value::String(s) => parse_input_integer(s)?,
+            _ => return Err(
+                "value must be an integer as a number or string, a boolean, or an array of them"
+                    .to_string(),
+            ),
         };
+    let prime = BigInt::from(prime.clone());
+    let reduced = ((value % &prime) + &prime) % &prime;
+    Ok(reduced.magnitude().clone())
+}
+
+fn parse_input_integer(s: &str) -> std::result::Result<BigInt, String> {
+    let (negative, digits) = match s.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, s),
+    };
+    let (radix, digits) = match digits
+        .strip_prefix("0x")
+        .or_else(|| digits.strip_prefix("0X"))
+    {
+        Some(hex) if !negative => (16, hex),
+        Some(_) => return Err(format!("'{}' is not an integer, hex can't be negative", s)),
+        None => (10, digits),
+    };
+    if digits.is_empty() || !digits.chars().all(|c| c.is_digit(radix)) {
+        return Err(format!("'{}' is not an integer", s));
     }
-    Ok(vals)
+    let magnitude = BigUint::parse_bytes(digits.as_bytes(), radix)
+        .ok_or_else(|| format!("'{}' is not an integer", s))?;
+    Ok(match negative {
+        true => -BigInt::from(magnitude),
+        false => BigInt::from(magnitude),
+    })
 }
 
+/// Parse the inputs JSON to the values of each input signal. Nested objects
+/// are flattened to dotted signal names, e.g. `tx.from` or `tx[0].from`.
 pub fn deserialize_inputs2<T: FieldOps>(
     inputs_data: &[u8],
     ff: &Field<T>,
-) -> Result<HashMap<String, Vec<T>>, Box<dyn std::error::Error>> {
+) -> Result<HashMap<String, Vec<T>>> {
     let v: serde_json::Value = serde_json::from_slice(inputs_data)?;
+    flatten_inputs(&v, &|v| parse_input_value(v, ff))
+}
 
+fn flatten_inputs<V>(
+    v: &serde_json::Value,
+    parse: &dyn Fn(&serde_json::Value) -> std::result::Result<V, String>,
+) -> Result<HashMap<String, Vec<V>>> {
     let map = if let serde_json::Value::Object(map) = v {
         map
     } else {
-        return Err(anyhow!("inputs must be an object").into());
+        return Err(Error::json("$", "inputs must be an object"));
     };
 
-    let mut inputs: HashMap<String, Vec<T>> = HashMap::new();
+    let mut signals = Vec::new();
     for (k, v) in map {
-        match v {
-            serde_json::Value::String(s) => {
-                let i = ff.parse_str(s.as_str())?;
-                inputs.insert(k.clone(), vec![i]);
-            }
-            serde_json::Value::Number(n) => {
-                if !n.is_u64() {
-                    return Err(anyhow!("signal value is not a positive integer").into());
-                }
-                let s = format!("{}", n.as_u64().unwrap());
-                let i = ff.parse_str(&s)?;
-                inputs.insert(k.clone(), vec![i]);
-            }
-            serde_json::Value::Array(ss) => {
-                let vals: Vec<T> = flatten_array2(k.as_str(), &ss, ff)?;
-                inputs.insert(k.clone(), vals);
-            }
-            _ => {
-                return Err(anyhow!(
-                    "value for key {} must be an a number as a string, as a number of an array of strings of numbers",
-                    k.clone()).into());
-            }
+        flatten_input(k.clone(), v, parse, &mut signals)?;
+    }
+
+    let mut inputs = HashMap::with_capacity(signals.len());
+    for (name, vals) in signals {
+        if inputs.contains_key(&name) {
+            return Err(Error::input(name, "given more than once"));
         }
+        inputs.insert(name, vals);
     }
     Ok(inputs)
 }
 
+/// What to do with input signals the inputs JSON doesn't give a value for
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MissingInputs {
+    /// Fail with [`Error::MissingInputs`], listing every missing signal
+    #[default]
+    Error,
+    /// Set every value of a missing signal to zero
+    Zero,
+}
+
 pub fn calc_witness_typed<T: FieldOps, NS: NodesStorage>(
     nodes: &Nodes<T, NS>,
     inputs: &str,
     input_mapping: &InputSignalsInfo,
     signals: &[usize],
-) -> Result<Vec<T>, Box<dyn std::error::Error>> {
+    missing: MissingInputs,
+) -> Result<Vec<T>> {
     let inputs = deserialize_inputs2(inputs.as_bytes(), &nodes.ff)?;
-    let inputs = create_inputs(&inputs, input_mapping)?;
+    evaluate_inputs(nodes, &inputs, input_mapping, signals, missing)
+}
+
+/// Evaluate the graph for the values of each input signal
+pub fn evaluate_inputs<T: FieldOps, NS: NodesStorage>(
+    nodes: &Nodes<T, NS>,
+    inputs: &HashMap<String, Vec<T>>,
+    input_mapping: &InputSignalsInfo,
+    signals: &[usize],
+    missing: MissingInputs,
+) -> Result<Vec<T>> {
+    let inputs = create_inputs_with(inputs, input_mapping, missing)?;
     let result = evaluate(&nodes.ff, &nodes.nodes, &inputs, signals, &nodes.constants);
     Ok(result)
 }
 
+/// Lay out the inputs as the graph expects them, an error if any input
+/// signal is missing
 pub fn create_inputs<T: FieldOps>(
     input_list: &HashMap<String, Vec<T>>,
     inputs_info: &InputSignalsInfo,
-) -> Result<Vec<T>, Box<dyn std::error::Error>> {
+) -> Result<Vec<T>> {
+    create_inputs_with(input_list, inputs_info, MissingInputs::Error)
+}
+
+pub fn create_inputs_with<T: FieldOps>(
+    input_list: &HashMap<String, Vec<T>>,
+    inputs_info: &InputSignalsInfo,
+    missing: MissingInputs,
+) -> Result<Vec<T>> {
+    if missing == MissingInputs::Error {
+        let mut absent: Vec<(&String, &(usize, usize))> = inputs_info
+            .iter()
+            .filter(|(name, _)| !input_list.contains_key(*name))
+            .collect();
+        if !absent.is_empty() {
+            // In the order the circuit declares them
+            absent.sort_by_key(|(_, (offset, _))| *offset);
+            return Err(Error::MissingInputs(
+                absent
+                    .into_iter()
+                    .map(|(name, (_, len))| (name.clone(), *len))
+                    .collect(),
+            ));
+        }
+    }
+
     let mut max_idx: usize = 0;
     for (offset, len) in inputs_info.values() {
         let idx = offset + len;
@@ -138,11 +282,14 @@ pub fn create_inputs<T: FieldOps>(
     inputs[0] = T::one();
     for (key, value) in input_list {
         if !inputs_info.contains_key(key) {
-            return Err(anyhow!("Unknown key in input: '{}'", key).into());
+            return Err(Error::input(key, "unknown input signal"));
         }
         let (offset, len) = inputs_info[key];
         if len != value.len() {
-            return Err(anyhow!("Invalid input length for key: '{}'", key).into());
+            return Err(Error::input(
+                key,
+                format!("expected {} values, got {}", len, value.len()),
+            ));
         }
 
         for (i, v) in value.iter().enumerate() {
@@ -152,91 +299,522 @@ pub fn create_inputs<T: FieldOps>(
     Ok(inputs)
 }
 
-pub fn calc_witness2<F: PrimeField>(
+/// Parse a field element from decimal, or hex with a `0x` prefix. Values
+/// must be less than the modulus, so every element has one encoding, and
+/// signs, whitespace and separators are rejected.
+pub fn parse_field_element<F: PrimeField>(value: &str) -> Result<F> {
+    let error = |message: &str| Error::FieldElement {
+        value: value.to_string(),
+        message: message.to_string(),
+    };
+    let (digits, radix) = match value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        Some(hex) => (hex, 16),
+        None => (value, 10),
+    };
+    if digits.is_empty() || !digits.chars().all(|c| c.is_digit(radix)) {
+        return Err(error("not a decimal or 0x hex number"));
+    }
+    let n = BigUint::parse_bytes(digits.as_bytes(), radix)
+        .ok_or_else(|| error("not a decimal or 0x hex number"))?;
+    let modulus: BigUint = F::MODULUS.into();
+    if n >= modulus {
+        return Err(error("not less than the field modulus"));
+    }
+    Ok(F::from_le_bytes_mod_order(&n.to_bytes_le()))
+}
+
+/// The number of public inputs `vk` takes, erroring if it has no IC points,
+/// not even the constant term
+pub(crate) fn n_public<E: Pairing>(vk: &VerifyingKey<E>) -> Result<usize> {
+    vk.gamma_abc_g1
+        .len()
+        .checked_sub(1)
+        .ok_or_else(|| Error::Mismatch("verifying key has no IC points".to_string()))
+}
+
+/// Compute the witness from the inputs JSON with a witness graph, an error
+/// if any input signal is missing
+pub fn calc_witness2<F: PrimeField>(inputs: &str, graph_data: &[u8]) -> Result<Vec<F>> {
+    calc_witness2_with(inputs, graph_data, MissingInputs::Error)
+}
+
+/// Same as [`calc_witness2`], with a choice of what to do with missing inputs
+pub fn calc_witness2_with<F: PrimeField>(
     inputs: &str,
     graph_data: &[u8],
-) -> Result<Vec<F>, Box<dyn std::error::Error>> {
-    //let start = std::time::Instant::now();
-    // let inputs = deserialize_inputs(inputs.as_bytes())?;
-    //println!("Inputs loaded in {:?}", start.elapsed());
-
-    //let start = std::time::Instant::now();
-    let (nodes, signals, input_mapping): (Box<dyn NodesInterface>, Vec<usize>, InputSignalsInfo) =
-        deserialize_witnesscalc_graph_from_bytes(graph_data).unwrap();
-    //println!("Graph loaded in {:?}", start.elapsed());
-
-    //let start = std::time::Instant::now();
-    // let mut inputs_buffer = get_inputs_buffer(nodes.get_inputs_size());
-    // populate_inputs(&inputs, &input_mapping, &mut inputs_buffer);
-    //println!("Inputs populated in {:?}", start.elapsed());
-
-    let nodes = nodes
-        .as_any()
-        .downcast_ref::<Nodes<U254, VecNodes>>()
-        .unwrap();
-    let result = calc_witness_typed(nodes, inputs, &input_mapping, &signals)?;
-    let vec_witness: Vec<F> = result
-        .iter()
-        .map(|a| F::from_le_bytes_mod_order(a.as_le_slice()))
-        .collect();
-    Ok(vec_witness)
+    missing: MissingInputs,
+) -> Result<Vec<F>> {
+    Graph::new::<F>(graph_data)?.evaluate(|nodes, input_mapping, signals| {
+        calc_witness_typed(nodes, inputs, input_mapping, signals, missing)
+    })
+}
+
+/// Same as [`calc_witness2`], with inputs built in Rust rather than JSON
+pub fn calc_witness_with_inputs<F: PrimeField>(
+    inputs: &Inputs<F>,
+    graph_data: &[u8],
+) -> Result<Vec<F>> {
+    let inputs = inputs.to_graph_values()?;
+    Graph::new::<F>(graph_data)?.evaluate(|nodes, input_mapping, signals| {
+        evaluate_inputs(nodes, &inputs, input_mapping, signals, MissingInputs::Error)
+    })
 }
 
-pub fn proof_oneshot(
+pub fn proof_oneshot<E: CircomCurve>(
     inputs_data: &str,
     pkey_data: &[u8],
     graph_data: &[u8],
     r1cs_data: &[u8],
-) -> (Proof<Bn254>, Vec<Bn254Fr>) {
-    let witness = calc_witness2(inputs_data, graph_data).unwrap();
-
-    let r1cs_reader = Cursor::new(r1cs_data);
-    let r1cs = R1CSFile::new(r1cs_reader).unwrap();
-
-    let pkey_reader = Cursor::new(pkey_data);
-    let pkey = ProvingKey::<Bn254>::deserialize_uncompressed_unchecked(pkey_reader).unwrap();
-
-    let circom = CircomCircuit::<Bn254Fr> {
-        r1cs: r1cs.into(),
-        witness: Some(witness),
-    };
+) -> Result<ProofWithInputs<E>> {
+    proof_oneshot_with_reduction::<E, LibsnarkReduction>(
+        inputs_data,
+        pkey_data,
+        graph_data,
+        r1cs_data,
+    )
+}
 
-    let public_inputs = circom.get_public_inputs().unwrap();
-    let mut rng = thread_rng();
+/// Same as [`proof_oneshot`], with inputs built in Rust rather than JSON
+pub fn proof_oneshot_with_inputs<E: CircomCurve>(
+    inputs: &Inputs<E::ScalarField>,
+    pkey_data: &[u8],
+    graph_data: &[u8],
+    r1cs_data: &[u8],
+) -> Result<ProofWithInputs<E>> {
+    let prover = Prover::<E, LibsnarkReduction>::new(pkey_data, graph_data, r1cs_data)?;
+    prover.prove_inputs(inputs)
+}
 
-    let proof = Groth16::<Bn254, LibsnarkReduction>::prove(&pkey, circom, &mut rng).unwrap();
+/// Same as [`proof_oneshot`], for a proving key made with the reduction `QAP`,
+/// e.g. [`crate::CircomReduction`] for keys that interoperate with snarkjs
+pub fn proof_oneshot_with_reduction<E: CircomCurve, QAP: R1CSToQAP>(
+    inputs_data: &str,
+    pkey_data: &[u8],
+    graph_data: &[u8],
+    r1cs_data: &[u8],
+) -> Result<ProofWithInputs<E>> {
+    let prover = Prover::<E, QAP>::new(pkey_data, graph_data, r1cs_data)?;
+    prover.prove(inputs_data)
+}
 
-    // Verify the proof can be rverified!
-    let pvk = prepare_verifying_key(&pkey.vk);
-    let result =
-        Groth16::<Bn254, LibsnarkReduction>::verify_proof(&pvk, &proof, &public_inputs).unwrap();
-    assert!(result);
+/// Prove a witness computed elsewhere, e.g. read with
+/// [`crate::wtns::witness_from_wtns`], so no witness graph is needed
+pub fn proof_from_witness<E: CircomCurve, QAP: R1CSToQAP>(
+    witness: Vec<E::ScalarField>,
+    pkey_data: &[u8],
+    r1cs_data: &[u8],
+) -> Result<ProofWithInputs<E>> {
+    let prover = Prover::<E, QAP>::without_graph(pkey_data, r1cs_data)?;
+    prover.prove_witness(witness)
+}
 
-    (proof, public_inputs)
+/// Generate a proving key for the circuit in an r1cs file, with fresh random
+/// toxic waste. Proofs must then be made with the same reduction `QAP`.
+pub fn setup_oneshot<E: CircomCurve, QAP: R1CSToQAP>(r1cs_data: &[u8]) -> Result<ProvingKey<E>> {
+    let r1cs: R1CS<E::ScalarField> = R1CSFile::new(Cursor::new(r1cs_data))?.try_into()?;
+    let circom = CircomCircuit {
+        r1cs,
+        witness: None,
+    };
+    Ok(Groth16::<E, QAP>::generate_random_parameters_with_reduction(circom, &mut thread_rng())?)
 }
 
-pub fn verify_proof(
+/// Groth16 verification doesn't depend on the QAP reduction, so this accepts
+/// proofs made with either [`LibsnarkReduction`] or [`crate::CircomReduction`].
+/// The key and proof are validated, so points off the curve or outside the
+/// prime order subgroup are rejected with an error.
+pub fn verify_proof<E: CircomCurve>(
     vkey_data: &[u8],
     proof_data: &[u8],
-    public_inputs: &[Bn254Fr],
+    public_inputs: &[E::ScalarField],
 ) -> Result<bool> {
     let vkey_reader = Cursor::new(vkey_data);
-    let vkey = VerifyingKey::<Bn254>::deserialize_uncompressed_unchecked(vkey_reader).unwrap();
+    let vkey =
+        VerifyingKey::<E>::deserialize_uncompressed(vkey_reader).map_err(|source| Error::Key {
+            kind: "verifying key",
+            source,
+        })?;
 
     let proof_reader = Cursor::new(proof_data);
-    let proof = Proof::<Bn254>::deserialize_uncompressed_unchecked(proof_reader).unwrap();
+    let proof = Proof::<E>::deserialize_uncompressed(proof_reader).map_err(Error::Proof)?;
 
     let pvk = prepare_verifying_key(&vkey);
-    let result = Groth16::<Bn254, LibsnarkReduction>::verify_proof(&pvk, &proof, public_inputs)?;
-    Ok(result)
+    verify_prepared(&pvk, &proof, public_inputs)
+}
+
+/// Verify a JSON proof in either this crate's format or snarkjs' format. A
+/// snarkjs proof doesn't include its public inputs, so needs
+/// [`verify_proof_json_with_public`].
+pub fn verify_proof_json<E: CircomCurve>(vkey_json: &str, proof_json: &str) -> Result<bool> {
+    verify_proof_json_with_public::<E>(vkey_json, proof_json, None)
 }
 
-pub fn verify_proof_json(vkey_json: &str, proof_json: &str) -> Result<bool> {
-    let (proof, public_inputs) = proof_from_json(proof_json)?;
-    let vkey = verifying_key_from_json(vkey_json)?;
+/// Verify a JSON proof with the public inputs from a snarkjs `public.json`.
+/// If the proof has its own inputs, they must match `public_json`.
+pub fn verify_proof_json_with_public<E: CircomCurve>(
+    vkey_json: &str,
+    proof_json: &str,
+    public_json: Option<&str>,
+) -> Result<bool> {
+    let (proof, public_inputs) = proof_and_inputs_from_json::<E>(proof_json, public_json)?;
+    let vkey = verifying_key_from_json::<E>(vkey_json)?;
 
     let pvk = prepare_verifying_key(&vkey);
-    let result = Groth16::<Bn254, LibsnarkReduction>::verify_proof(&pvk, &proof, &public_inputs)?;
+    verify_prepared(&pvk, &proof, &public_inputs)
+}
 
-    Ok(result)
+/// Read a JSON proof in either format with its public inputs, which for a
+/// snarkjs proof come from `public_json`. If the proof has its own inputs,
+/// they must match `public_json`.
+pub fn proof_and_inputs_from_json<E: CircomCurve>(
+    proof_json: &str,
+    public_json: Option<&str>,
+) -> Result<(Proof<E>, Vec<E::ScalarField>)> {
+    let public_inputs = public_json
+        .map(public_inputs_from_json::<E::ScalarField>)
+        .transpose()?;
+    match (is_snarkjs_proof(proof_json)?, public_inputs) {
+        (true, Some(public_inputs)) => {
+            Ok((proof_from_snarkjs_json::<E>(proof_json)?, public_inputs))
+        }
+        (true, None) => Err(Error::Verification(
+            "a snarkjs proof needs its public inputs from public.json".to_string(),
+        )),
+        (false, public_inputs) => {
+            let (proof, inputs) = proof_from_json::<E>(proof_json)?;
+            if public_inputs.is_some_and(|public_inputs| public_inputs != inputs) {
+                return Err(Error::Mismatch(
+                    "public.json doesn't match the inputs in the proof".to_string(),
+                ));
+            }
+            Ok((proof, inputs))
+        }
+    }
+}
+
+pub(crate) fn verify_prepared<E: CircomCurve>(
+    pvk: &PreparedVerifyingKey<E>,
+    proof: &Proof<E>,
+    public_inputs: &[E::ScalarField],
+) -> Result<bool> {
+    let expected = n_public(&pvk.vk)?;
+    if public_inputs.len() != expected {
+        return Err(Error::Verification(format!(
+            "expected {} public inputs, got {}",
+            expected,
+            public_inputs.len()
+        )));
+    }
+    Ok(Groth16::<E, LibsnarkReduction>::verify_proof(
+        pvk,
+        proof,
+        public_inputs,
+    )?)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::qap::CircomReduction;
+    use crate::{proof_from_json, proof_from_snarkjs_json, proof_to_json, proof_to_snarkjs_json};
+    use crate::{verifying_key_from_json, verifying_key_to_json, verifying_key_to_snarkjs_json};
+    use ark_bn254::{g2, Bn254, Fq2, Fr, G2Affine};
+    use ark_ec::short_weierstrass::SWCurveConfig;
+    use ark_ff::Field;
+    use ark_serialize::CanonicalSerialize;
+
+    static R1CS_DATA: &[u8] = include_bytes!("../example/multiply.r1cs");
+
+    /// A point on the BN254 G2 curve, but outside its prime order subgroup
+    pub(crate) fn g2_outside_subgroup() -> G2Affine {
+        (1u64..)
+            .find_map(|x| {
+                let x = Fq2::from(x);
+                let y = (x * x * x + g2::Config::COEFF_B).sqrt()?;
+                let point = G2Affine::new_unchecked(x, y);
+                (!point.is_in_correct_subgroup_assuming_on_curve()).then_some(point)
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn inputs_cover_the_full_range() {
+        let prime: BigUint = Fr::MODULUS.into();
+        let parse = |json: &str| {
+            let value: serde_json::Value = serde_json::from_str(json).unwrap();
+            canonical_input(&value, &prime)
+        };
+        let big = "123456789012345678901234567890123456789";
+
+        assert_eq!(parse("12").unwrap(), BigUint::from(12u32));
+        assert_eq!(parse("\"0x1F\"").unwrap(), BigUint::from(31u32));
+        assert_eq!(parse("true").unwrap(), BigUint::from(1u32));
+        assert_eq!(parse("false").unwrap(), BigUint::from(0u32));
+        assert_eq!(
+            parse(big).unwrap().to_string(),
+            big,
+            "not rounded through f64"
+        );
+        assert_eq!(parse(&format!("\"{}\"", big)).unwrap().to_string(), big);
+        assert_eq!(parse("-1").unwrap(), &prime - 1u32);
+        assert_eq!(parse("\"-2\"").unwrap(), &prime - 2u32);
+        assert_eq!(
+            parse(&format!("\"{}\"", prime)).unwrap(),
+            BigUint::from(0u32)
+        );
+
+        for bad in [
+            "1.5", "1e3", "\"\"", "\"12a\"", "\"-0x1\"", "\" 1\"", "null", "{}",
+        ] {
+            assert!(parse(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn nested_inputs_are_flattened() {
+        let prime: BigUint = Fr::MODULUS.into();
+        let flatten = |json: &str| {
+            let value: serde_json::Value = serde_json::from_str(json).unwrap();
+            flatten_inputs(&value, &|v| canonical_input(v, &prime))
+        };
+        let n = |vs: &[u32]| vs.iter().map(|v| BigUint::from(*v)).collect::<Vec<_>>();
+
+        let inputs = flatten(
+            r#"{"a": 1, "tx": {"from": 2, "amount": [3, [4, 5]], "to": {"key": "6"}},
+                "txs": [{"from": 7}, {"from": 8, "memo": [9]}]}"#,
+        )
+        .unwrap();
+        assert_eq!(inputs.len(), 7);
+        assert_eq!(inputs["a"], n(&[1]));
+        assert_eq!(inputs["tx.from"], n(&[2]));
+        assert_eq!(inputs["tx.amount"], n(&[3, 4, 5]));
+        assert_eq!(inputs["tx.to.key"], n(&[6]));
+        assert_eq!(inputs["txs[0].from"], n(&[7]));
+        assert_eq!(inputs["txs[1].memo"], n(&[9]));
+
+        // A 2-D array of buses
+        let inputs =
+            flatten(r#"{"tx": [[{"from": 1}, {"from": 2}], [{"from": 3, "to": [4]}]]}"#).unwrap();
+        assert_eq!(inputs.len(), 4);
+        assert_eq!(inputs["tx[0][0].from"], n(&[1]));
+        assert_eq!(inputs["tx[0][1].from"], n(&[2]));
+        assert_eq!(inputs["tx[1][0].to"], n(&[4]));
+
+        // Empty nested arrays have no values
+        let inputs = flatten(r#"{"a": [[]], "b": [[], [1, []], 2]}"#).unwrap();
+        assert_eq!(inputs["a"], n(&[]));
+        assert_eq!(inputs["b"], n(&[1, 2]));
+
+        let error_key = |json: &str| match flatten(json).unwrap_err() {
+            Error::Input { key, .. } => key,
+            e => panic!("{}", e),
+        };
+        assert_eq!(error_key(r#"{"tx": {"from": "x"}}"#), "tx.from");
+        assert_eq!(
+            error_key(r#"{"tx": {"amount": [1, [2, "x"]]}}"#),
+            "tx.amount[1][1]"
+        );
+        assert_eq!(
+            error_key(r#"{"txs": [{"from": 1}, {"from": 1.5}]}"#),
+            "txs[1].from"
+        );
+        assert_eq!(error_key(r#"{"txs": [{"from": 1}, 2]}"#), "txs[1]");
+        assert_eq!(error_key(r#"{"txs": [[{"from": 1}], [2]]}"#), "txs[1]");
+        assert_eq!(error_key(r#"{"txs": [[{"from": 1}, 2]]}"#), "txs[0][1]");
+        assert_eq!(error_key(r#"{"tx.from": 1, "tx": {"from": 2}}"#), "tx.from");
+    }
+
+    #[test]
+    fn missing_inputs_are_errors() {
+        use circom_witnesscalc::field::U254;
+
+        let info: InputSignalsInfo = [
+            ("a".to_string(), (1, 1)),
+            ("b".to_string(), (2, 3)),
+            ("c".to_string(), (5, 1)),
+        ]
+        .into_iter()
+        .collect();
+        let given: HashMap<String, Vec<U254>> = [("b".to_string(), vec![U254::from(7u64); 3])]
+            .into_iter()
+            .collect();
+
+        let err = create_inputs(&given, &info).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "missing input signals: a (1 value), c (1 value)"
+        );
+        assert!(matches!(err, Error::MissingInputs(ref m) if m[0] == ("a".to_string(), 1)));
+
+        let inputs = create_inputs_with(&given, &info, MissingInputs::Zero).unwrap();
+        assert_eq!(inputs[1], U254::ZERO);
+        assert_eq!(inputs[2..5], [U254::from(7u64); 3]);
+    }
+
+    #[test]
+    fn prove_wtns_witness() {
+        let pk = setup_oneshot::<Bn254, LibsnarkReduction>(R1CS_DATA).unwrap();
+        let mut pkey_data = Vec::new();
+        pk.serialize_uncompressed(&mut pkey_data).unwrap();
+
+        let witness = [1u64, 110, 11, 10].map(Fr::from);
+        let wtns = crate::wtns::witness_to_wtns(&witness);
+        let witness = crate::wtns::witness_from_wtns(Cursor::new(&wtns)).unwrap();
+        let (proof, public_inputs) =
+            proof_from_witness::<Bn254, LibsnarkReduction>(witness, &pkey_data, R1CS_DATA).unwrap();
+        assert_eq!(public_inputs, vec![Fr::from(110), Fr::from(11)]);
+        let pvk = prepare_verifying_key(&pk.vk);
+        assert!(verify_prepared(&pvk, &proof, &public_inputs).unwrap());
+        assert!(matches!(
+            verify_prepared(&pvk, &proof, &public_inputs[..1]),
+            Err(Error::Verification(_))
+        ));
+
+        // A key without IC points fails as it does when written
+        let mut empty = pk.vk.clone();
+        empty.gamma_abc_g1.clear();
+        assert!(matches!(
+            verify_prepared(&prepare_verifying_key(&empty), &proof, &[]),
+            Err(Error::Mismatch(_))
+        ));
+
+        let prover = Prover::<Bn254>::without_graph(&pkey_data, R1CS_DATA).unwrap();
+        assert!(matches!(
+            prover.prove_witness(vec![Fr::from(1), Fr::from(110)]),
+            Err(Error::Mismatch(_))
+        ));
+        assert!(matches!(
+            prover.calc_witness(r#"{"a": "11", "b": "10"}"#),
+            Err(Error::Graph(_))
+        ));
+    }
+
+    #[test]
+    fn verification_rejects_invalid_points() {
+        let pk = setup_oneshot::<Bn254, CircomReduction>(R1CS_DATA).unwrap();
+        let mut vk_data = Vec::new();
+        pk.vk.serialize_uncompressed(&mut vk_data).unwrap();
+
+        // Valid points which aren't a proof fail the pairing check
+        let proof = Proof::<Bn254> {
+            a: pk.vk.alpha_g1,
+            b: pk.vk.beta_g2,
+            c: pk.vk.alpha_g1,
+        };
+        let mut proof_data = Vec::new();
+        proof.serialize_uncompressed(&mut proof_data).unwrap();
+        let inputs = [Fr::from(110), Fr::from(11)];
+        assert!(!verify_proof::<Bn254>(&vk_data, &proof_data, &inputs).unwrap());
+
+        // An x coordinate which isn't on the curve doesn't deserialize
+        proof_data[..32].fill(1);
+        assert!(matches!(
+            verify_proof::<Bn254>(&vk_data, &proof_data, &inputs),
+            Err(Error::Proof(_))
+        ));
+
+        vk_data[..32].fill(1);
+        assert!(matches!(
+            verify_proof::<Bn254>(&vk_data, &proof_data, &inputs),
+            Err(Error::Key { .. })
+        ));
+    }
+
+    #[test]
+    fn verification_rejects_points_outside_the_subgroup() {
+        let pk = setup_oneshot::<Bn254, CircomReduction>(R1CS_DATA).unwrap();
+        let bad = g2_outside_subgroup();
+        assert!(bad.is_on_curve());
+
+        let mut vk_data = Vec::new();
+        pk.vk.serialize_uncompressed(&mut vk_data).unwrap();
+        let proof = Proof::<Bn254> {
+            a: pk.vk.alpha_g1,
+            b: bad,
+            c: pk.vk.alpha_g1,
+        };
+        let mut proof_data = Vec::new();
+        proof.serialize_uncompressed(&mut proof_data).unwrap();
+        let inputs = vec![Fr::from(110), Fr::from(11)];
+        assert!(matches!(
+            verify_proof::<Bn254>(&vk_data, &proof_data, &inputs),
+            Err(Error::Proof(_))
+        ));
+
+        let mut vk = pk.vk.clone();
+        vk.delta_g2 = bad;
+        let mut vk_data = Vec::new();
+        vk.serialize_uncompressed(&mut vk_data).unwrap();
+        let mut proof_data = Vec::new();
+        pk.vk
+            .alpha_g1
+            .serialize_uncompressed(&mut proof_data)
+            .unwrap();
+        pk.vk
+            .beta_g2
+            .serialize_uncompressed(&mut proof_data)
+            .unwrap();
+        pk.vk
+            .alpha_g1
+            .serialize_uncompressed(&mut proof_data)
+            .unwrap();
+        assert!(matches!(
+            verify_proof::<Bn254>(&vk_data, &proof_data, &inputs),
+            Err(Error::Key { .. })
+        ));
+
+        // Both JSON formats check the subgroup too
+        let path = |result: Result<()>| match result {
+            Err(Error::Json { path, .. }) => path,
+            _ => panic!("expected a JSON error"),
+        };
+        let json = proof_to_json(&proof, &inputs).unwrap();
+        assert_eq!(path(proof_from_json::<Bn254>(&json).map(drop)), "$.b");
+        let json = proof_to_snarkjs_json(&proof).unwrap();
+        assert_eq!(
+            path(proof_from_snarkjs_json::<Bn254>(&json).map(drop)),
+            "$.pi_b"
+        );
+        for json in [
+            verifying_key_to_json(&vk).unwrap(),
+            verifying_key_to_snarkjs_json(&vk).unwrap(),
+        ] {
+            assert_eq!(
+                path(verifying_key_from_json::<Bn254>(&json).map(drop)),
+                "$.vk_delta_2"
+            );
+        }
+    }
+
+    #[test]
+    fn field_elements_are_canonical() {
+        let p = Fr::MODULUS.to_string();
+        let p_minus_1 = (-Fr::from(1)).to_string();
+        assert_eq!(parse_field_element::<Fr>("110").unwrap(), Fr::from(110));
+        assert_eq!(parse_field_element::<Fr>("0x6e").unwrap(), Fr::from(110));
+        assert_eq!(parse_field_element::<Fr>("0X6E").unwrap(), Fr::from(110));
+        assert_eq!(parse_field_element::<Fr>(&p_minus_1).unwrap(), -Fr::from(1));
+
+        for bad in [
+            &p, "", "0x", "-1", "+1", " 1", "1 ", "1_0", "1e3", "0x6g", "abc",
+        ] {
+            assert!(
+                matches!(
+                    parse_field_element::<Fr>(bad),
+                    Err(Error::FieldElement { .. })
+                ),
+                "{:?} should be rejected",
+                bad
+            );
+        }
+
+        let public = format!(r#"["1", "{}"]"#, p);
+        match crate::public_inputs_from_json::<Fr>(&public) {
+            Err(Error::Json { path, .. }) => assert_eq!(path, "$[1]"),
+            _ => panic!("expected the modulus to be rejected"),
+        }
+    }
 }
@@ -0,0 +1,153 @@
+//! snarkjs `.wtns` witness files, and the witness as JSON
+//! Spec: <https://github.com/iden3/snarkjs/blob/master/src/wtns_utils.js>
+//!
+//! The witness is every wire of the circuit in r1cs order, starting with the
+//! constant 1, then the public outputs and inputs, then the private signals.
+
+use ark_ff::PrimeField;
+
+use ark_std::io::{Read, Seek};
+
+use crate::binfile::{le_bytes, OffsetReader, Sections};
+use crate::error::{Error, Result};
+use crate::snarkjs::to_snarkjs_string;
+
+/// Every `.wtns` file starts with these bytes
+pub const MAGIC: &[u8; 4] = b"wtns";
+
+const VERSION: u32 = 2;
+const HEADER_SECTION: u32 = 1;
+const WITNESS_SECTION: u32 = 2;
+
+/// Bytes per field element, the modulus rounded up to whole 64-bit words
+pub(crate) fn field_size<F: PrimeField>() -> u32 {
+    F::MODULUS_BIT_SIZE.div_ceil(64) * 8
+}
+
+fn write_section(out: &mut Vec<u8>, sec_type: u32, data: &[u8]) {
+    out.extend_from_slice(&sec_type.to_le_bytes());
+    out.extend_from_slice(&(data.len() as u64).to_le_bytes());
+    out.extend_from_slice(data);
+}
+
+/// Encode a witness as a `.wtns` file, as written by circom's witness generators
+/// and read by `snarkjs groth16 prove`
+pub fn witness_to_wtns<F: PrimeField>(witness: &[F]) -> Vec<u8> {
+    let n8 = field_size::<F>();
+    let element = |value: F| {
+        le_bytes(value.into_bigint(), n8 as usize).expect("field element fits in its field size")
+    };
+
+    let mut header = Vec::with_capacity(8 + n8 as usize);
+    header.extend_from_slice(&n8.to_le_bytes());
+    header.extend(le_bytes(F::MODULUS, n8 as usize).expect("modulus fits in its field size"));
+    header.extend_from_slice(&(witness.len() as u32).to_le_bytes());
+
+    let mut values = Vec::with_capacity(witness.len() * n8 as usize);
+    for value in witness {
+        values.extend(element(*value));
+    }
+
+    let mut out = Vec::with_capacity(12 + 2 * 12 + header.len() + values.len());
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&VERSION.to_le_bytes());
+    out.extend_from_slice(&2u32.to_le_bytes());
+    write_section(&mut out, HEADER_SECTION, &header);
+    write_section(&mut out, WITNESS_SECTION, &values);
+    out
+}
+
+/// Read a `.wtns` file, e.g. from circom's C++ witness generator. The prime in
+/// its header must be the modulus of `F`, and every value less than it.
+pub fn witness_from_wtns<F: PrimeField, R: Read + Seek>(reader: R) -> Result<Vec<F>> {
+    let mut reader = OffsetReader::new(reader, |offset, message| Error::Wtns { offset, message });
+    let sections = Sections::read(&mut reader, MAGIC, VERSION)?;
+
+    let size = sections.seek(&mut reader, HEADER_SECTION, "header")?;
+    let n8 = reader.read_u32()?;
+    if n8 != field_size::<F>() || size != 8 + n8 as u64 {
+        return Err(reader.error(format!("Invalid field size {}", n8)));
+    }
+    reader.read_prime::<F>(n8)?;
+    let n_witness = reader.read_u32()?;
+
+    let size = sections.seek(&mut reader, WITNESS_SECTION, "witness")?;
+    if size != n_witness as u64 * n8 as u64 {
+        return Err(reader.error(format!(
+            "Witness section has {} bytes, expected {} values of {} bytes",
+            size, n_witness, n8
+        )));
+    }
+    (0..n_witness).map(|_| reader.read_field(n8)).collect()
+}
+
+/// The witness as a JSON list of decimal strings, as `snarkjs wtns export json`
+pub fn witness_to_json<F: PrimeField>(witness: &[F]) -> Result<String> {
+    let strings: Vec<String> = witness.iter().map(|w| w.to_string()).collect();
+    to_snarkjs_string(&strings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bn254::Fr;
+    use std::io::Cursor;
+
+    #[test]
+    fn wtns_layout() {
+        let witness = [Fr::from(1u64), Fr::from(33u64), -Fr::from(1u64)];
+        let wtns = witness_to_wtns(&witness);
+
+        assert_eq!(&wtns[..4], b"wtns");
+        assert_eq!(wtns[4..12], [2, 0, 0, 0, 2, 0, 0, 0]);
+        // Header section: type, size, n8, prime, number of wires
+        assert_eq!(wtns[12..16], [1, 0, 0, 0]);
+        assert_eq!(wtns[16..24], 40u64.to_le_bytes());
+        assert_eq!(wtns[24..28], 32u32.to_le_bytes());
+        assert_eq!(
+            wtns[28..60],
+            le_bytes(Fr::MODULUS, 32).unwrap()[..],
+            "prime is little-endian"
+        );
+        assert_eq!(wtns[60..64], 3u32.to_le_bytes());
+        // Witness section
+        assert_eq!(wtns[64..68], [2, 0, 0, 0]);
+        assert_eq!(wtns[68..76], 96u64.to_le_bytes());
+        assert_eq!(wtns[76], 1);
+        assert_eq!(wtns[108], 33);
+        assert_eq!(wtns.len(), 76 + 96);
+
+        let json: Vec<String> = serde_json::from_str(&witness_to_json(&witness).unwrap()).unwrap();
+        assert_eq!(json[1], "33");
+        assert_eq!(json[2], (-Fr::from(1u64)).to_string());
+    }
+
+    #[test]
+    fn wtns_round_trip() {
+        let witness = vec![Fr::from(1u64), Fr::from(33u64), -Fr::from(1u64)];
+        let wtns = witness_to_wtns(&witness);
+        assert_eq!(
+            witness_from_wtns::<Fr, _>(Cursor::new(&wtns)).unwrap(),
+            witness
+        );
+
+        // Witness for another curve's field
+        let mut bad = wtns.clone();
+        bad[59] ^= 1;
+        let err = witness_from_wtns::<Fr, _>(Cursor::new(&bad)).unwrap_err();
+        assert!(
+            err.to_string().contains("does not match the field modulus"),
+            "{}",
+            err
+        );
+
+        // A value equal to the prime isn't canonical
+        let mut bad = wtns.clone();
+        bad[76..108].copy_from_slice(&wtns[28..60]);
+        let err = witness_from_wtns::<Fr, _>(Cursor::new(&bad)).unwrap_err();
+        assert!(matches!(err, Error::Wtns { offset: 108, .. }), "{}", err);
+
+        let err = witness_from_wtns::<Fr, _>(Cursor::new(&wtns[..wtns.len() - 1])).unwrap_err();
+        assert!(matches!(err, Error::Wtns { .. }), "{}", err);
+    }
+}
@@ -0,0 +1,667 @@
+//! snarkjs Groth16 `.zkey` reader
+//! Spec: <https://github.com/iden3/snarkjs/blob/master/src/zkey_utils.js>
+
+// From https://github.com/arkworks-rs/circom-compat/blob/3c95ed98e23a408b4d99a53e483a9bba39685a4e/src/zkey.rs
+// Made generic over the curve, and reading through the same checked reader as the r1cs
+
+use ark_ec::short_weierstrass::{Affine, SWCurveConfig};
+use ark_ec::CurveConfig;
+use ark_ff::{Field, PrimeField, Zero};
+use ark_groth16::{ProvingKey, VerifyingKey};
+
+use ark_std::io::{Read, Seek};
+
+use crate::binfile::{OffsetReader, Sections};
+use crate::constraints::Constraints;
+use crate::curve::CircomCurve;
+use crate::error::{Error, Result};
+use crate::r1cs_reader::R1CS;
+
+/// Every `.zkey` file starts with these bytes
+pub const MAGIC: &[u8; 4] = b"zkey";
+
+/// A proving key from a snarkjs Phase 2 ceremony, and the constraints it was made for
+///
+/// The zkey only stores the A and B matrices, so every constraint in `r1cs` has
+/// an empty C. Proofs must be made with [`crate::qap::CircomReduction`], which
+/// never reads C.
+pub struct ZKey<E: CircomCurve> {
+    pub proving_key: ProvingKey<E>,
+    pub r1cs: R1CS<E::ScalarField>,
+}
+
+struct GrothHeader {
+    n8q: u32,
+    n8r: u32,
+    n_vars: u32,
+    n_public: u32,
+    domain_size: u32,
+}
+
+impl<E: CircomCurve> ZKey<E> {
+    /// reader must implement the Seek trait, for example with a Cursor
+    pub fn new<R: Read + Seek>(reader: R) -> Result<ZKey<E>> {
+        let mut reader =
+            OffsetReader::new(reader, |offset, message| Error::Zkey { offset, message });
+        let sections = Sections::read(&mut reader, MAGIC, 1)?;
+
+        sections.seek(&mut reader, 1, "header")?;
+        let protocol = reader.read_u32()?;
+        if protocol != 1 {
+            return Err(reader.error(format!("Protocol {} is not Groth16", protocol)));
+        }
+
+        let size = sections.seek(&mut reader, 2, "Groth16 header")?;
+        let n8q = read_field_size(&mut reader, size)?;
+        reader.read_prime::<Fq<E::G1Config>>(n8q)?;
+        let n8r = read_field_size(&mut reader, size)?;
+        reader.read_prime::<E::ScalarField>(n8r)?;
+        let header = GrothHeader {
+            n8q,
+            n8r,
+            n_vars: reader.read_u32()?,
+            n_public: reader.read_u32()?,
+            domain_size: reader.read_u32()?,
+        };
+        let g1_size = point_size::<E::G1Config>(header.n8q);
+        let g2_size = point_size::<E::G2Config>(header.n8q);
+        if size != 20 + n8q as u64 + n8r as u64 + 3 * g1_size + 3 * g2_size {
+            return Err(reader.error("Invalid Groth16 header section size"));
+        }
+        if header.n_public >= header.n_vars {
+            return Err(reader.error("More public signals than variables"));
+        }
+
+        let alpha_g1 = read_g1::<_, E>(&mut reader, &header)?;
+        let beta_g1 = read_g1::<_, E>(&mut reader, &header)?;
+        let beta_g2 = read_g2::<_, E>(&mut reader, &header)?;
+        let gamma_g2 = read_g2::<_, E>(&mut reader, &header)?;
+        let delta_g1 = read_g1::<_, E>(&mut reader, &header)?;
+        let delta_g2 = read_g2::<_, E>(&mut reader, &header)?;
+
+        let n_public = header.n_public as u64;
+        let n_vars = header.n_vars as u64;
+        let size = sections.seek(&mut reader, 3, "IC")?;
+        let gamma_abc_g1 = read_section_points::<_, E::G1Config>(
+            &mut reader,
+            size,
+            "IC",
+            n_public + 1,
+            header.n8q,
+        )?;
+
+        let size = sections.seek(&mut reader, 5, "A")?;
+        let a_query =
+            read_section_points::<_, E::G1Config>(&mut reader, size, "A", n_vars, header.n8q)?;
+
+        let size = sections.seek(&mut reader, 6, "B1")?;
+        let b_g1_query =
+            read_section_points::<_, E::G1Config>(&mut reader, size, "B1", n_vars, header.n8q)?;
+
+        let size = sections.seek(&mut reader, 7, "B2")?;
+        let b_g2_query =
+            read_section_points::<_, E::G2Config>(&mut reader, size, "B2", n_vars, header.n8q)?;
+
+        let size = sections.seek(&mut reader, 8, "C")?;
+        let n_private = n_vars - n_public - 1;
+        let l_query =
+            read_section_points::<_, E::G1Config>(&mut reader, size, "C", n_private, header.n8q)?;
+
+        // Bounds the domain size by the file's length, before reading coefficients
+        let size = sections.seek(&mut reader, 9, "H")?;
+        let domain_size = header.domain_size as u64;
+        let h_query =
+            read_section_points::<_, E::G1Config>(&mut reader, size, "H", domain_size, header.n8q)?;
+
+        let size = sections.seek(&mut reader, 4, "coefficients")?;
+        let r1cs = read_coefficients::<_, E::ScalarField>(&mut reader, size, &header)?;
+
+        let vk = VerifyingKey {
+            alpha_g1,
+            beta_g2,
+            gamma_g2,
+            delta_g2,
+            gamma_abc_g1,
+        };
+        Ok(ZKey {
+            proving_key: ProvingKey {
+                vk,
+                beta_g1,
+                delta_g1,
+                a_query,
+                b_g1_query,
+                b_g2_query,
+                h_query,
+                l_query,
+            },
+            r1cs,
+        })
+    }
+
+    /// Check an r1cs is the circuit this zkey was made for, with the same
+    /// counts and the same A and B coefficients in every constraint
+    pub fn check_r1cs(&self, r1cs: &R1CS<E::ScalarField>) -> Result<()> {
+        let counts = [
+            ("public inputs", self.r1cs.num_inputs, r1cs.num_inputs),
+            ("variables", self.r1cs.num_variables, r1cs.num_variables),
+            (
+                "constraints",
+                self.r1cs.constraints.len(),
+                r1cs.constraints.len(),
+            ),
+        ];
+        for (name, zkey, r1cs) in counts {
+            if zkey != r1cs {
+                return Err(Error::Mismatch(format!(
+                    "zkey has {} {}, but the r1cs has {}",
+                    zkey, name, r1cs
+                )));
+            }
+        }
+        for (i, (zkey, r1cs)) in self
+            .r1cs
+            .constraints
+            .iter()
+            .zip(&r1cs.constraints)
+            .enumerate()
+        {
+            for (name, zkey, r1cs) in [("A", &zkey.0, &r1cs.0), ("B", &zkey.1, &r1cs.1)] {
+                if sorted_terms(zkey) != sorted_terms(r1cs) {
+                    return Err(Error::Mismatch(format!(
+                        "constraint {} has a different {} in the zkey and the r1cs",
+                        i, name
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The nonzero terms of a linear combination, ordered by signal
+fn sorted_terms<F: PrimeField>(terms: &[(usize, F)]) -> Vec<(usize, F)> {
+    let mut terms: Vec<_> = terms
+        .iter()
+        .filter(|(_, v)| !v.is_zero())
+        .copied()
+        .collect();
+    terms.sort_by_key(|(signal, _)| *signal);
+    terms
+}
+
+type Fq<P> = <<P as CurveConfig>::BaseField as Field>::BasePrimeField;
+
+fn read_field_size<R: Read + Seek>(reader: &mut OffsetReader<R>, size: u64) -> Result<u32> {
+    let n8 = reader.read_u32()?;
+    if n8 == 0 || n8 % 8 != 0 || n8 as u64 > size {
+        return Err(reader.error(format!("Invalid field size {}", n8)));
+    }
+    Ok(n8)
+}
+
+fn point_size<P: SWCurveConfig>(n8q: u32) -> u64 {
+    2 * P::BaseField::extension_degree() * n8q as u64
+}
+
+/// snarkjs stores field elements in Montgomery form, `x·R` where `R = 2^(8·n8)`
+fn montgomery_r_inv<R: Read + Seek, F: PrimeField>(
+    reader: &mut OffsetReader<R>,
+    n8: u32,
+) -> Result<F> {
+    let mut r = vec![0u8; n8 as usize];
+    r.push(1);
+    F::from_le_bytes_mod_order(&r)
+        .inverse()
+        .ok_or_else(|| reader.error("Montgomery radix is not invertible"))
+}
+
+/// Read the `n` points of a section of `size` bytes, which must be exactly
+/// their size so a count from the header can't be larger than the file
+fn read_section_points<R: Read + Seek, P: SWCurveConfig>(
+    reader: &mut OffsetReader<R>,
+    size: u64,
+    name: &str,
+    n: u64,
+    n8q: u32,
+) -> Result<Vec<Affine<P>>> {
+    if n.checked_mul(point_size::<P>(n8q)) != Some(size) {
+        return Err(reader.error(format!("Invalid {} section size", name)));
+    }
+    read_points(reader, n, n8q)
+}
+
+/// Points are `x` then `y`, extension field coordinates are written `c0` first.
+/// The point at infinity is written as `(0, 0)`. Every other point must be in
+/// the prime order subgroup.
+fn read_points<R: Read + Seek, P: SWCurveConfig>(
+    reader: &mut OffsetReader<R>,
+    n: u64,
+    n8q: u32,
+) -> Result<Vec<Affine<P>>> {
+    let r_inv = montgomery_r_inv::<R, Fq<P>>(reader, n8q)?;
+    let coordinate = |reader: &mut OffsetReader<R>| -> Result<P::BaseField> {
+        let mut elems = Vec::new();
+        for _ in 0..P::BaseField::extension_degree() {
+            elems.push(reader.read_field::<Fq<P>>(n8q)? * r_inv);
+        }
+        P::BaseField::from_base_prime_field_elems(elems)
+            .ok_or_else(|| reader.error("Invalid point coordinate"))
+    };
+    let mut points = Vec::with_capacity(n as usize);
+    for _ in 0..n {
+        let x = coordinate(reader)?;
+        let y = coordinate(reader)?;
+        if x.is_zero() && y.is_zero() {
+            points.push(Affine::identity());
+            continue;
+        }
+        let point = Affine::new_unchecked(x, y);
+        if !point.is_on_curve() || !point.is_in_correct_subgroup_assuming_on_curve() {
+            return Err(reader.error("Point is not in the curve subgroup"));
+        }
+        points.push(point);
+    }
+    Ok(points)
+}
+
+fn read_g1<R: Read + Seek, E: CircomCurve>(
+    reader: &mut OffsetReader<R>,
+    header: &GrothHeader,
+) -> Result<E::G1Affine> {
+    Ok(read_points::<R, E::G1Config>(reader, 1, header.n8q)?[0])
+}
+
+fn read_g2<R: Read + Seek, E: CircomCurve>(
+    reader: &mut OffsetReader<R>,
+    header: &GrothHeader,
+) -> Result<E::G2Affine> {
+    Ok(read_points::<R, E::G2Config>(reader, 1, header.n8q)?[0])
+}
+
+/// Read the A and B matrices. snarkjs appends one constraint per public input
+/// (and the constant one) after the circuit's own, each `A = [(i, 1)]` with an
+/// empty B, which are checked and dropped here as the QAP reduction adds them back.
+fn read_coefficients<R: Read + Seek, F: PrimeField>(
+    reader: &mut OffsetReader<R>,
+    size: u64,
+    header: &GrothHeader,
+) -> Result<R1CS<F>> {
+    let n_coeffs = reader.read_u32()?;
+    if size != 4 + n_coeffs as u64 * (12 + header.n8r as u64) {
+        return Err(reader.error("Invalid coefficients section size"));
+    }
+
+    // Coefficients are doubly Montgomery encoded, `x·R²`
+    let r_inv = montgomery_r_inv::<R, F>(reader, header.n8r)?;
+    let r_inv2 = r_inv * r_inv;
+
+    let mut constraints: Vec<Constraints<F>> = Vec::new();
+    for _ in 0..n_coeffs {
+        let matrix = reader.read_u32()?;
+        let constraint = reader.read_u32()?;
+        let signal = reader.read_u32()?;
+        if constraint >= header.domain_size {
+            return Err(reader.error(format!(
+                "Coefficient for constraint {}, but the domain size is {}",
+                constraint, header.domain_size
+            )));
+        }
+        if signal >= header.n_vars {
+            return Err(reader.error(format!(
+                "Coefficient for signal {}, but there are only {} signals",
+                signal, header.n_vars
+            )));
+        }
+        let value = reader.read_field::<F>(header.n8r)? * r_inv2;
+
+        // Below the domain size, which the H section's length bounds
+        let constraint = constraint as usize;
+        if constraint >= constraints.len() {
+            constraints.resize_with(constraint + 1, Default::default);
+        }
+        match matrix {
+            0 => constraints[constraint].0.push((signal as usize, value)),
+            1 => constraints[constraint].1.push((signal as usize, value)),
+            _ => return Err(reader.error(format!("Invalid matrix {}", matrix))),
+        }
+    }
+
+    let num_inputs = header.n_public as usize + 1;
+    let num_constraints = constraints
+        .len()
+        .checked_sub(num_inputs)
+        .ok_or_else(|| reader.error("Missing the public input constraints"))?;
+    for (i, (a, b, _)) in constraints[num_constraints..].iter().enumerate() {
+        if a[..] != [(i, F::ONE)] || !b.is_empty() {
+            return Err(reader.error(format!(
+                "Constraint {} is not the public input constraint for signal {}",
+                num_constraints + i,
+                i
+            )));
+        }
+    }
+    constraints.truncate(num_constraints);
+
+    Ok(R1CS {
+        num_inputs,
+        num_aux: header.n_vars as usize - num_inputs,
+        num_variables: header.n_vars as usize,
+        constraints,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuit::CircomCircuit;
+    use crate::qap::CircomReduction;
+    use crate::r1cs_reader::R1CSFile;
+    use crate::utils::tests::g2_outside_subgroup;
+    use ark_bn254::{Bn254, Fr};
+    use ark_crypto_primitives::snark::SNARK;
+    use ark_ec::AffineRepr;
+    use ark_ff::BigInteger;
+    use ark_groth16::Groth16;
+    use ark_std::io::Cursor;
+    use ark_std::rand::thread_rng;
+
+    fn multiply_r1cs() -> R1CS<Fr> {
+        let data = include_bytes!("../example/multiply.r1cs");
+        R1CSFile::new(Cursor::new(&data[..]))
+            .unwrap()
+            .try_into()
+            .unwrap()
+    }
+
+    fn montgomery<F: PrimeField>(x: F, times: u32, n8: usize) -> Vec<u8> {
+        let mut r = vec![0u8; n8];
+        r.push(1);
+        let r = F::from_le_bytes_mod_order(&r);
+        let mut x = x;
+        for _ in 0..times {
+            x *= r;
+        }
+        x.into_bigint().to_bytes_le()
+    }
+
+    fn write_points<P: SWCurveConfig>(out: &mut Vec<u8>, points: &[Affine<P>]) {
+        for p in points {
+            let (x, y) = p.xy().unwrap_or_default();
+            for c in [x, y] {
+                for e in c.to_base_prime_field_elements() {
+                    out.extend(montgomery(e, 1, 32));
+                }
+            }
+        }
+    }
+
+    /// Encode a zkey the same way snarkjs does
+    fn write_zkey(pk: &ProvingKey<Bn254>, r1cs: &R1CS<Fr>) -> Vec<u8> {
+        let mut sections: Vec<(u32, Vec<u8>)> = vec![(1, 1u32.to_le_bytes().to_vec())];
+
+        let n_public = (r1cs.num_inputs - 1) as u32;
+        let mut header = Vec::new();
+        header.extend(32u32.to_le_bytes());
+        header.extend(ark_bn254::Fq::MODULUS.to_bytes_le());
+        header.extend(32u32.to_le_bytes());
+        header.extend(Fr::MODULUS.to_bytes_le());
+        header.extend((r1cs.num_variables as u32).to_le_bytes());
+        header.extend(n_public.to_le_bytes());
+        header.extend((pk.h_query.len() as u32).to_le_bytes());
+        write_points(&mut header, &[pk.vk.alpha_g1, pk.beta_g1]);
+        write_points(&mut header, &[pk.vk.beta_g2, pk.vk.gamma_g2]);
+        write_points(&mut header, &[pk.delta_g1]);
+        write_points(&mut header, &[pk.vk.delta_g2]);
+        sections.push((2, header));
+
+        let mut ic = Vec::new();
+        write_points(&mut ic, &pk.vk.gamma_abc_g1);
+        sections.push((3, ic));
+
+        let mut coeffs = Vec::new();
+        let mut n_coeffs = 0u32;
+        let mut coeff = |matrix: u32, constraint: usize, signal: usize, value: Fr| {
+            coeffs.extend(matrix.to_le_bytes());
+            coeffs.extend((constraint as u32).to_le_bytes());
+            coeffs.extend((signal as u32).to_le_bytes());
+            coeffs.extend(montgomery(value, 2, 32));
+            n_coeffs += 1;
+        };
+        for (i, (a, b, _)) in r1cs.constraints.iter().enumerate() {
+            a.iter().for_each(|(s, v)| coeff(0, i, *s, *v));
+            b.iter().for_each(|(s, v)| coeff(1, i, *s, *v));
+        }
+        for s in 0..r1cs.num_inputs {
+            coeff(0, r1cs.constraints.len() + s, s, Fr::from(1));
+        }
+        let mut section = n_coeffs.to_le_bytes().to_vec();
+        section.extend(coeffs);
+        sections.push((4, section));
+
+        for (sec_type, points) in [(5, &pk.a_query), (6, &pk.b_g1_query)] {
+            let mut section = Vec::new();
+            write_points(&mut section, points);
+            sections.push((sec_type, section));
+        }
+        let mut b2 = Vec::new();
+        write_points(&mut b2, &pk.b_g2_query);
+        sections.push((7, b2));
+        for (sec_type, points) in [(8, &pk.l_query), (9, &pk.h_query)] {
+            let mut section = Vec::new();
+            write_points(&mut section, points);
+            sections.push((sec_type, section));
+        }
+
+        let mut out = MAGIC.to_vec();
+        out.extend(1u32.to_le_bytes());
+        out.extend((sections.len() as u32).to_le_bytes());
+        for (sec_type, data) in sections {
+            out.extend(sec_type.to_le_bytes());
+            out.extend((data.len() as u64).to_le_bytes());
+            out.extend(data);
+        }
+        out
+    }
+
+    /// Offset of the data of the section of type `sec_type`, after its size
+    fn section_offset(data: &[u8], sec_type: u32) -> usize {
+        let u32_at = |i: usize| u32::from_le_bytes(data[i..i + 4].try_into().unwrap());
+        let mut offset = 12;
+        loop {
+            let size = u64::from_le_bytes(data[offset + 4..offset + 12].try_into().unwrap());
+            if u32_at(offset) == sec_type {
+                return offset + 12;
+            }
+            offset += 12 + size as usize;
+        }
+    }
+
+    #[test]
+    fn read_and_prove() {
+        let r1cs = multiply_r1cs();
+        let circuit = CircomCircuit {
+            r1cs: r1cs.clone(),
+            witness: None,
+        };
+        let mut pk = Groth16::<Bn254, CircomReduction>::generate_random_parameters_with_reduction(
+            circuit,
+            &mut thread_rng(),
+        )
+        .unwrap();
+        // snarkjs writes an H point for every element of the domain, see
+        // `read_snarkjs_zkey`, arkworks one fewer as the last is never used
+        pk.h_query.push(Affine::identity());
+
+        let zkey = ZKey::<Bn254>::new(Cursor::new(write_zkey(&pk, &r1cs))).unwrap();
+        assert!(zkey.proving_key == pk);
+        assert_eq!(zkey.r1cs.num_inputs, r1cs.num_inputs);
+        assert_eq!(zkey.r1cs.num_variables, r1cs.num_variables);
+        for (zkey, r1cs) in zkey.r1cs.constraints.iter().zip(&r1cs.constraints) {
+            assert_eq!(zkey.0, r1cs.0);
+            assert_eq!(zkey.1, r1cs.1);
+            assert!(zkey.2.is_empty());
+        }
+        zkey.check_r1cs(&r1cs).unwrap();
+
+        let circuit = CircomCircuit {
+            r1cs,
+            witness: Some([1, 110, 11, 10].map(Fr::from).to_vec()),
+        };
+        let inputs = circuit.get_public_inputs().unwrap();
+        let proof =
+            Groth16::<Bn254, CircomReduction>::prove(&zkey.proving_key, circuit, &mut thread_rng())
+                .unwrap();
+        assert!(
+            Groth16::<Bn254, CircomReduction>::verify(&zkey.proving_key.vk, &inputs, &proof)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn read_snarkjs_zkey() {
+        let data = include_bytes!("../example/snarkjs/multiplier.zkey");
+        let zkey = ZKey::<Bn254>::new(Cursor::new(&data[..])).unwrap();
+        let data = include_bytes!("../example/snarkjs/multiplier.r1cs");
+        let r1cs: R1CS<Fr> = R1CSFile::new(Cursor::new(&data[..]))
+            .unwrap()
+            .try_into()
+            .unwrap();
+        zkey.check_r1cs(&r1cs).unwrap();
+        assert_eq!(zkey.proving_key.h_query.len(), 4);
+
+        // a = 3, b = 11, only c is public
+        let circuit = CircomCircuit {
+            r1cs,
+            witness: Some([1, 33, 3, 11].map(Fr::from).to_vec()),
+        };
+        let inputs = circuit.get_public_inputs().unwrap();
+        assert_eq!(inputs, vec![Fr::from(33)]);
+        let proof =
+            Groth16::<Bn254, CircomReduction>::prove(&zkey.proving_key, circuit, &mut thread_rng())
+                .unwrap();
+        assert!(
+            Groth16::<Bn254, CircomReduction>::verify(&zkey.proving_key.vk, &inputs, &proof)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn check_r1cs_compares_coefficients() {
+        let data = include_bytes!("../example/snarkjs/multiplier.zkey");
+        let zkey = ZKey::<Bn254>::new(Cursor::new(&data[..])).unwrap();
+        let data = include_bytes!("../example/snarkjs/multiplier.r1cs");
+        let r1cs: R1CS<Fr> = R1CSFile::new(Cursor::new(&data[..]))
+            .unwrap()
+            .try_into()
+            .unwrap();
+
+        // The order of the terms doesn't matter
+        let mut reordered = r1cs.clone();
+        reordered.constraints[0].0.reverse();
+        reordered.constraints[0].1.reverse();
+        zkey.check_r1cs(&reordered).unwrap();
+
+        // Same counts, different coefficients
+        for matrix in ["A", "B"] {
+            let mut other = r1cs.clone();
+            let terms = match matrix {
+                "A" => &mut other.constraints[0].0,
+                _ => &mut other.constraints[0].1,
+            };
+            terms[0].1 += Fr::from(1);
+            match zkey.check_r1cs(&other) {
+                Err(Error::Mismatch(message)) => assert_eq!(
+                    message,
+                    format!(
+                        "constraint 0 has a different {} in the zkey and the r1cs",
+                        matrix
+                    )
+                ),
+                _ => panic!("expected a mismatch in {}", matrix),
+            }
+        }
+
+        // Same counts, a different signal
+        let mut other = r1cs.clone();
+        other.constraints[0].1[0].0 = 1;
+        assert!(matches!(zkey.check_r1cs(&other), Err(Error::Mismatch(_))));
+    }
+
+    #[test]
+    fn corrupt_zkeys_are_errors() {
+        let r1cs = multiply_r1cs();
+        let pk = Groth16::<Bn254, CircomReduction>::generate_random_parameters_with_reduction(
+            CircomCircuit {
+                r1cs: r1cs.clone(),
+                witness: None,
+            },
+            &mut thread_rng(),
+        )
+        .unwrap();
+        let data = write_zkey(&pk, &r1cs);
+
+        // Truncated file
+        assert!(ZKey::<Bn254>::new(Cursor::new(&data[..data.len() - 8])).is_err());
+
+        // Counts from the header bounded only by section sizes past the end of the
+        // file, which would allocate gigabytes for the H points
+        let mut huge = data.clone();
+        let header = section_offset(&huge, 2);
+        huge[header + 80..header + 84].copy_from_slice(&(1u32 << 28).to_le_bytes());
+        let h = section_offset(&huge, 9);
+        huge[h - 8..h].copy_from_slice(&((1u64 << 28) * 64).to_le_bytes());
+        match ZKey::<Bn254>::new(Cursor::new(huge)) {
+            Err(Error::Zkey { message, .. }) => assert!(message.contains("past the end")),
+            _ => panic!("expected a section past the end of the file"),
+        }
+
+        // The last public input constraint with the wrong coefficient, or the
+        // wrong signal, is a real constraint that mustn't be dropped
+        let coeffs = section_offset(&data, 4);
+        let n_coeffs = u32::from_le_bytes(data[coeffs..coeffs + 4].try_into().unwrap());
+        let last = coeffs + 4 + (n_coeffs as usize - 1) * 44;
+        let mut wrong_value = data.clone();
+        wrong_value[last + 12..last + 44].copy_from_slice(&montgomery(Fr::from(2), 2, 32));
+        let mut wrong_signal = data.clone();
+        wrong_signal[last + 8..last + 12].copy_from_slice(&0u32.to_le_bytes());
+        for data in [wrong_value, wrong_signal] {
+            match ZKey::<Bn254>::new(Cursor::new(data)) {
+                Err(Error::Zkey { message, .. }) => assert_eq!(
+                    message,
+                    format!(
+                        "Constraint {} is not the public input constraint for signal {}",
+                        r1cs.constraints.len() + r1cs.num_inputs - 1,
+                        r1cs.num_inputs - 1
+                    )
+                ),
+                _ => panic!("expected a bad public input constraint to be rejected"),
+            }
+        }
+
+        // A B2 point on the curve, but outside the subgroup
+        let mut bad = pk.clone();
+        bad.b_g2_query[1] = g2_outside_subgroup();
+        match ZKey::<Bn254>::new(Cursor::new(write_zkey(&bad, &r1cs))) {
+            Err(Error::Zkey { message, .. }) => {
+                assert_eq!(message, "Point is not in the curve subgroup")
+            }
+            _ => panic!("expected a point outside the subgroup to be rejected"),
+        }
+
+        // An r1cs file isn't a zkey
+        let data = include_bytes!("../example/multiply.r1cs");
+        match ZKey::<Bn254>::new(Cursor::new(&data[..])) {
+            Err(Error::Zkey { offset, .. }) => assert_eq!(offset, 4),
+            _ => panic!("expected a zkey format error"),
+        }
+
+        // Drop a constraint from the r1cs
+        let mut short = r1cs.clone();
+        short.constraints.clear();
+        let zkey = ZKey::<Bn254> {
+            proving_key: pk,
+            r1cs,
+        };
+        assert!(matches!(zkey.check_r1cs(&short), Err(Error::Mismatch(_))));
+    }
+}